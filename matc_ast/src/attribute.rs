@@ -0,0 +1,35 @@
+use matc_span::Spanned;
+use std::fmt;
+
+/// `#[level(lint, ...)]` on a definition, overriding lint levels within it
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Attribute {
+    Allow(Vec<Spanned<String>>),
+    Warn(Vec<Spanned<String>>),
+    Deny(Vec<Spanned<String>>),
+}
+
+impl Attribute {
+    pub fn lints(&self) -> &[Spanned<String>] {
+        match self {
+            Attribute::Allow(lints) | Attribute::Warn(lints) | Attribute::Deny(lints) => lints,
+        }
+    }
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Attribute::Allow(_) => "allow",
+            Attribute::Warn(_) => "warn",
+            Attribute::Deny(_) => "deny",
+        };
+        let lints = self
+            .lints()
+            .iter()
+            .map(|lint| lint.value.as_str())
+            .collect::<Vec<_>>();
+        write!(f, "#[{}({})]", name, lints.join(", "))
+    }
+}
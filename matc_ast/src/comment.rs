@@ -0,0 +1,20 @@
+use std::fmt;
+
+/// Trivia ignored by the compiler but kept for tools like the formatter
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Comment {
+    /// `// ...` without the slashes
+    Line(String),
+    /// `/* ... */` without the delimiters
+    Block(String),
+}
+
+impl fmt::Display for Comment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Comment::Line(text) => write!(f, "//{}", text),
+            Comment::Block(text) => write!(f, "/*{}*/", text),
+        }
+    }
+}
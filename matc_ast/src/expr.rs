@@ -2,6 +2,7 @@ use crate::Type;
 use matc_span::Spanned;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     /// ||
     Or(Spanned<Self>, Spanned<Self>),
@@ -59,8 +60,12 @@ pub enum Expr {
     /// Atom
     I8(i8),
     I32(i32),
+    /// UTF-8 text, whose bytes are laid out as a `char` array
     String(String),
     Variable(String),
+
+    /// Placeholder for an expression which failed to parse
+    Error,
 }
 
 impl Expr {
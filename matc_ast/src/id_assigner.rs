@@ -0,0 +1,87 @@
+use crate::visit::{walk_expr_mut, walk_param_mut, walk_stmt_mut, VisitorMut};
+use crate::{Expr, Param, Stmt};
+use matc_span::{NodeId, Spanned};
+
+/// Numbers statements, expressions and defined names in visiting order.
+///
+/// Keep using the same one for nodes parsed later, e.g. in the interactive mode, so that their IDs
+/// do not collide with the previous ones.
+#[derive(Debug, Default)]
+pub struct IdAssigner {
+    next: u32,
+}
+
+impl IdAssigner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn next_id(&mut self) -> NodeId {
+        let id = NodeId::new(self.next);
+        self.next += 1;
+        id
+    }
+}
+
+impl VisitorMut for IdAssigner {
+    fn visit_stmt_mut(&mut self, stmt: &mut Spanned<Stmt>) {
+        stmt.id = self.next_id();
+        if let Stmt::DefFn { name, .. } | Stmt::DefVar { name, .. } = stmt.value_mut() {
+            name.id = self.next_id();
+        }
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Spanned<Expr>) {
+        expr.id = self.next_id();
+        walk_expr_mut(self, expr);
+    }
+
+    fn visit_param_mut(&mut self, param: &mut Param) {
+        param.name.id = self.next_id();
+        walk_param_mut(self, param);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visit::{walk_expr, Visitor};
+
+    #[derive(Default)]
+    struct Collector(Vec<NodeId>);
+
+    impl Visitor<'_> for Collector {
+        fn visit_expr(&mut self, expr: &Spanned<Expr>) {
+            self.0.push(expr.id);
+            walk_expr(self, expr);
+        }
+    }
+
+    #[test]
+    fn test_assign() {
+        let var = |name: &str| Spanned::any(Expr::Variable(name.to_string()));
+        let mut stmt = Spanned::any(Stmt::DefVar {
+            attrs: vec![],
+            is_mut: false,
+            name: Spanned::any("x".to_string()),
+            ty: Spanned::any(crate::Type::I32),
+            expr: Some(Spanned::any(Expr::Add(var("a"), var("b")))),
+        });
+        let mut ids = IdAssigner::new();
+        ids.visit_stmt_mut(&mut stmt);
+        let Stmt::DefVar { name, .. } = stmt.value() else {
+            unreachable!()
+        };
+        assert_eq!((stmt.id, name.id), (NodeId::new(0), NodeId::new(1)));
+
+        let mut collector = Collector::default();
+        collector.visit_stmt(&stmt);
+        assert_eq!(collector.0, [2, 3, 4].map(NodeId::new));
+
+        // Numbering continues on later nodes.
+        let mut expr = var("c");
+        ids.visit_expr_mut(&mut expr);
+        assert_eq!(expr.id, NodeId::new(5));
+    }
+}
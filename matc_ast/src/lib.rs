@@ -1,16 +1,32 @@
+mod attribute;
+mod comment;
 mod expr;
+mod id_assigner;
 mod param;
 mod stmt;
 mod ty;
+pub mod visit;
 
+pub use attribute::Attribute;
+pub use comment::Comment;
 pub use expr::Expr;
+pub use id_assigner::IdAssigner;
 pub use param::Param;
 pub use stmt::Stmt;
-pub use ty::Type;
+pub use ty::{FnType, Type};
 
 use matc_span::Spanned;
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ast {
     pub defs: Vec<Spanned<Stmt>>,
+    pub comments: Vec<Spanned<Comment>>,
+}
+
+/// An input of the interactive mode
+#[derive(Debug, PartialEq, Clone)]
+pub enum Line {
+    Defs(Vec<Spanned<Stmt>>),
+    Expr(Spanned<Expr>),
 }
@@ -2,6 +2,7 @@ use crate::Type;
 use matc_span::Spanned;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Param {
     pub is_mut: bool,
     pub name: Spanned<String>,
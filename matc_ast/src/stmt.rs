@@ -1,11 +1,13 @@
-use crate::{Expr, Param, Type};
+use crate::{Attribute, Expr, Param, Type};
 use matc_span::Spanned;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Stmt {
     Empty,
 
     DefFn {
+        attrs: Vec<Spanned<Attribute>>,
         name: Spanned<String>,
         args: Vec<Param>,
         ret_ty: Spanned<Type>,
@@ -13,6 +15,7 @@ pub enum Stmt {
     },
 
     DefVar {
+        attrs: Vec<Spanned<Attribute>>,
         is_mut: bool,
         name: Spanned<String>,
         ty: Spanned<Type>,
@@ -53,4 +56,7 @@ pub enum Stmt {
     ShrAssign(Spanned<Expr>, Spanned<Expr>),
 
     Expr(Spanned<Expr>),
+
+    /// Placeholder for a statement which failed to parse
+    Error,
 }
@@ -1,6 +1,54 @@
+use std::fmt;
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     Void,
     I8,
     I32,
+    Fn(FnType),
+    /// A placeholder for an unknown type, which has been reported as a syntax error
+    Error,
+}
+
+/// The signature of a function
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FnType {
+    pub params: Vec<Type>,
+    pub ret: Box<Type>,
+}
+
+impl Type {
+    /// Whether the type is or contains `Type::Error`
+    pub fn has_error(&self) -> bool {
+        match self {
+            Type::Error => true,
+            Type::Fn(fn_ty) => fn_ty.params.iter().any(Type::has_error) || fn_ty.ret.has_error(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Void => write!(f, "void"),
+            Type::I8 => write!(f, "char"),
+            Type::I32 => write!(f, "i32"),
+            Type::Fn(fn_ty) => write!(f, "{}", fn_ty),
+            Type::Error => write!(f, "{{unknown}}"),
+        }
+    }
+}
+
+impl fmt::Display for FnType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let params = self
+            .params
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        write!(f, "fn({}) -> {}", params.join(", "), self.ret)
+    }
 }
@@ -0,0 +1,345 @@
+use crate::{Attribute, Expr, Param, Stmt, Type};
+use matc_span::Spanned;
+
+/// Traverses the AST by reference.
+///
+/// Every method defaults to the `walk_*` function visiting the children, so a pass overrides only
+/// the nodes it is interested in and calls `walk_*` to keep going into their children.
+pub trait Visitor<'a>: Sized {
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        walk_stmt(self, stmt);
+    }
+    fn visit_expr(&mut self, expr: &'a Spanned<Expr>) {
+        walk_expr(self, expr);
+    }
+    fn visit_param(&mut self, param: &'a Param) {
+        walk_param(self, param);
+    }
+    fn visit_ty(&mut self, _ty: &'a Spanned<Type>) {}
+    fn visit_attr(&mut self, _attr: &'a Spanned<Attribute>) {}
+}
+
+pub fn walk_stmt<'a, V: Visitor<'a>>(visitor: &mut V, stmt: &'a Spanned<Stmt>) {
+    match stmt.value() {
+        Stmt::DefFn {
+            attrs,
+            args,
+            ret_ty,
+            body,
+            ..
+        } => {
+            for attr in attrs {
+                visitor.visit_attr(attr);
+            }
+            for arg in args {
+                visitor.visit_param(arg);
+            }
+            visitor.visit_ty(ret_ty);
+            visitor.visit_stmt(body);
+        }
+        Stmt::DefVar {
+            attrs, ty, expr, ..
+        } => {
+            for attr in attrs {
+                visitor.visit_attr(attr);
+            }
+            visitor.visit_ty(ty);
+            if let Some(expr) = expr {
+                visitor.visit_expr(expr);
+            }
+        }
+        Stmt::Block(stmts) => {
+            for stmt in stmts {
+                visitor.visit_stmt(stmt);
+            }
+        }
+        Stmt::If { cond, then, els } => {
+            visitor.visit_expr(cond);
+            visitor.visit_stmt(then);
+            if let Some(els) = els {
+                visitor.visit_stmt(els);
+            }
+        }
+        Stmt::Return(expr) => {
+            if let Some(expr) = expr {
+                visitor.visit_expr(expr);
+            }
+        }
+        Stmt::Assign(lhs, rhs)
+        | Stmt::AddAssign(lhs, rhs)
+        | Stmt::SubAssign(lhs, rhs)
+        | Stmt::MulAssign(lhs, rhs)
+        | Stmt::DivAssign(lhs, rhs)
+        | Stmt::RemAssign(lhs, rhs)
+        | Stmt::BitAndAssign(lhs, rhs)
+        | Stmt::BitOrAssign(lhs, rhs)
+        | Stmt::BitXorAssign(lhs, rhs)
+        | Stmt::ShlAssign(lhs, rhs)
+        | Stmt::ShrAssign(lhs, rhs) => {
+            visitor.visit_expr(lhs);
+            visitor.visit_expr(rhs);
+        }
+        Stmt::Expr(expr) => visitor.visit_expr(expr),
+        Stmt::Empty | Stmt::Error => {}
+    }
+}
+
+pub fn walk_expr<'a, V: Visitor<'a>>(visitor: &mut V, expr: &'a Spanned<Expr>) {
+    match expr.value() {
+        Expr::Or(lhs, rhs)
+        | Expr::And(lhs, rhs)
+        | Expr::Lt(lhs, rhs)
+        | Expr::Gt(lhs, rhs)
+        | Expr::Lte(lhs, rhs)
+        | Expr::Gte(lhs, rhs)
+        | Expr::Eq(lhs, rhs)
+        | Expr::Neq(lhs, rhs)
+        | Expr::BitOr(lhs, rhs)
+        | Expr::BitXor(lhs, rhs)
+        | Expr::BitAnd(lhs, rhs)
+        | Expr::Shl(lhs, rhs)
+        | Expr::Shr(lhs, rhs)
+        | Expr::Add(lhs, rhs)
+        | Expr::Sub(lhs, rhs)
+        | Expr::Mul(lhs, rhs)
+        | Expr::Div(lhs, rhs)
+        | Expr::Rem(lhs, rhs) => {
+            visitor.visit_expr(lhs);
+            visitor.visit_expr(rhs);
+        }
+        Expr::As(expr, ty) => {
+            visitor.visit_expr(expr);
+            visitor.visit_ty(ty);
+        }
+        Expr::FnCall { name, args } => {
+            visitor.visit_expr(name);
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expr::I8(_) | Expr::I32(_) | Expr::String(_) | Expr::Variable(_) | Expr::Error => {}
+    }
+}
+
+pub fn walk_param<'a, V: Visitor<'a>>(visitor: &mut V, param: &'a Param) {
+    visitor.visit_ty(&param.ty);
+}
+
+/// Traverses the AST by mutable reference to rewrite it in place; the mutable counterpart of
+/// `Visitor`.
+pub trait VisitorMut: Sized {
+    fn visit_stmt_mut(&mut self, stmt: &mut Spanned<Stmt>) {
+        walk_stmt_mut(self, stmt);
+    }
+    fn visit_expr_mut(&mut self, expr: &mut Spanned<Expr>) {
+        walk_expr_mut(self, expr);
+    }
+    fn visit_param_mut(&mut self, param: &mut Param) {
+        walk_param_mut(self, param);
+    }
+    fn visit_ty_mut(&mut self, _ty: &mut Spanned<Type>) {}
+    fn visit_attr_mut(&mut self, _attr: &mut Spanned<Attribute>) {}
+}
+
+pub fn walk_stmt_mut<V: VisitorMut>(visitor: &mut V, stmt: &mut Spanned<Stmt>) {
+    match stmt.value_mut() {
+        Stmt::DefFn {
+            attrs,
+            args,
+            ret_ty,
+            body,
+            ..
+        } => {
+            for attr in attrs {
+                visitor.visit_attr_mut(attr);
+            }
+            for arg in args {
+                visitor.visit_param_mut(arg);
+            }
+            visitor.visit_ty_mut(ret_ty);
+            visitor.visit_stmt_mut(body);
+        }
+        Stmt::DefVar {
+            attrs, ty, expr, ..
+        } => {
+            for attr in attrs {
+                visitor.visit_attr_mut(attr);
+            }
+            visitor.visit_ty_mut(ty);
+            if let Some(expr) = expr {
+                visitor.visit_expr_mut(expr);
+            }
+        }
+        Stmt::Block(stmts) => {
+            for stmt in stmts {
+                visitor.visit_stmt_mut(stmt);
+            }
+        }
+        Stmt::If { cond, then, els } => {
+            visitor.visit_expr_mut(cond);
+            visitor.visit_stmt_mut(then);
+            if let Some(els) = els {
+                visitor.visit_stmt_mut(els);
+            }
+        }
+        Stmt::Return(expr) => {
+            if let Some(expr) = expr {
+                visitor.visit_expr_mut(expr);
+            }
+        }
+        Stmt::Assign(lhs, rhs)
+        | Stmt::AddAssign(lhs, rhs)
+        | Stmt::SubAssign(lhs, rhs)
+        | Stmt::MulAssign(lhs, rhs)
+        | Stmt::DivAssign(lhs, rhs)
+        | Stmt::RemAssign(lhs, rhs)
+        | Stmt::BitAndAssign(lhs, rhs)
+        | Stmt::BitOrAssign(lhs, rhs)
+        | Stmt::BitXorAssign(lhs, rhs)
+        | Stmt::ShlAssign(lhs, rhs)
+        | Stmt::ShrAssign(lhs, rhs) => {
+            visitor.visit_expr_mut(lhs);
+            visitor.visit_expr_mut(rhs);
+        }
+        Stmt::Expr(expr) => visitor.visit_expr_mut(expr),
+        Stmt::Empty | Stmt::Error => {}
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut>(visitor: &mut V, expr: &mut Spanned<Expr>) {
+    match expr.value_mut() {
+        Expr::Or(lhs, rhs)
+        | Expr::And(lhs, rhs)
+        | Expr::Lt(lhs, rhs)
+        | Expr::Gt(lhs, rhs)
+        | Expr::Lte(lhs, rhs)
+        | Expr::Gte(lhs, rhs)
+        | Expr::Eq(lhs, rhs)
+        | Expr::Neq(lhs, rhs)
+        | Expr::BitOr(lhs, rhs)
+        | Expr::BitXor(lhs, rhs)
+        | Expr::BitAnd(lhs, rhs)
+        | Expr::Shl(lhs, rhs)
+        | Expr::Shr(lhs, rhs)
+        | Expr::Add(lhs, rhs)
+        | Expr::Sub(lhs, rhs)
+        | Expr::Mul(lhs, rhs)
+        | Expr::Div(lhs, rhs)
+        | Expr::Rem(lhs, rhs) => {
+            visitor.visit_expr_mut(lhs);
+            visitor.visit_expr_mut(rhs);
+        }
+        Expr::As(expr, ty) => {
+            visitor.visit_expr_mut(expr);
+            visitor.visit_ty_mut(ty);
+        }
+        Expr::FnCall { name, args } => {
+            visitor.visit_expr_mut(name);
+            for arg in args {
+                visitor.visit_expr_mut(arg);
+            }
+        }
+        Expr::I8(_) | Expr::I32(_) | Expr::String(_) | Expr::Variable(_) | Expr::Error => {}
+    }
+}
+
+pub fn walk_param_mut<V: VisitorMut>(visitor: &mut V, param: &mut Param) {
+    visitor.visit_ty_mut(&mut param.ty);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Spanned<Expr> {
+        Spanned::any(Expr::Variable(name.to_string()))
+    }
+
+    /// `fn f(x: i32) -> i32 { if x { return g(x)(y as i32); } let z: char = x + 1; }`
+    fn defn() -> Spanned<Stmt> {
+        let call = Expr::FnCall {
+            name: Spanned::any(Expr::FnCall {
+                name: var("g"),
+                args: vec![var("x")],
+            }),
+            args: vec![Spanned::any(Expr::As(var("y"), Spanned::any(Type::I32)))],
+        };
+        Spanned::any(Stmt::DefFn {
+            attrs: vec![],
+            name: Spanned::any("f".to_string()),
+            args: vec![Param {
+                is_mut: false,
+                name: Spanned::any("x".to_string()),
+                ty: Spanned::any(Type::I32),
+            }],
+            ret_ty: Spanned::any(Type::I32),
+            body: Spanned::any(Stmt::Block(vec![
+                Spanned::any(Stmt::If {
+                    cond: var("x"),
+                    then: Spanned::any(Stmt::Return(Some(Spanned::any(call)))),
+                    els: None,
+                }),
+                Spanned::any(Stmt::DefVar {
+                    attrs: vec![],
+                    is_mut: false,
+                    name: Spanned::any("z".to_string()),
+                    ty: Spanned::any(Type::I8),
+                    expr: Some(Spanned::any(Expr::Add(
+                        var("x"),
+                        Spanned::any(Expr::I32(1)),
+                    ))),
+                }),
+            ])),
+        })
+    }
+
+    #[derive(Default)]
+    struct Collector<'a> {
+        vars: Vec<&'a str>,
+        tys: Vec<&'a Type>,
+    }
+
+    impl<'a> Visitor<'a> for Collector<'a> {
+        fn visit_expr(&mut self, expr: &'a Spanned<Expr>) {
+            if let Expr::Variable(name) = expr.value() {
+                self.vars.push(name);
+            }
+            walk_expr(self, expr);
+        }
+        fn visit_ty(&mut self, ty: &'a Spanned<Type>) {
+            self.tys.push(ty.value());
+        }
+    }
+
+    #[test]
+    fn test_visitor() {
+        let defn = defn();
+        let mut collector = Collector::default();
+        collector.visit_stmt(&defn);
+        assert_eq!(collector.vars, vec!["x", "g", "x", "y", "x"]);
+        assert_eq!(
+            collector.tys,
+            vec![&Type::I32, &Type::I32, &Type::I32, &Type::I8]
+        );
+    }
+
+    struct Renamer;
+
+    impl VisitorMut for Renamer {
+        fn visit_expr_mut(&mut self, expr: &mut Spanned<Expr>) {
+            if let Expr::Variable(name) = expr.value_mut() {
+                name.make_ascii_uppercase();
+            }
+            walk_expr_mut(self, expr);
+        }
+    }
+
+    #[test]
+    fn test_visitor_mut() {
+        let mut defn = defn();
+        Renamer.visit_stmt_mut(&mut defn);
+        let mut collector = Collector::default();
+        collector.visit_stmt(&defn);
+        assert_eq!(collector.vars, vec!["X", "G", "X", "Y", "X"]);
+    }
+}
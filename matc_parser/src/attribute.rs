@@ -0,0 +1,63 @@
+use crate::ident::{ident, keyword};
+use crate::prelude::*;
+use matc_ast::Attribute;
+use matc_span::Spanned;
+
+// #[allow(lint1, lint2)]
+pub(crate) fn attribute() -> impl Parser<Spanned<Attribute>> {
+    let level = choice((
+        keyword("allow").to(Attribute::Allow as fn(_) -> _),
+        keyword("warn").to(Attribute::Warn as fn(_) -> _),
+        keyword("deny").to(Attribute::Deny as fn(_) -> _),
+    ))
+    .padded();
+    let lints = ident()
+        .map_with_span(Spanned::new)
+        .separated_by(just(',').padded())
+        .allow_trailing()
+        .delimited_by(just('('), just(')'));
+
+    just("#[")
+        .ignore_then(level)
+        .then(lints.padded())
+        .then_ignore(just(']'))
+        .map(|(level, lints)| level(lints))
+        .map_with_span(Spanned::new)
+        .labelled("attribute")
+        .padded()
+        .boxed()
+}
+
+pub(crate) fn attributes() -> impl Parser<Vec<Spanned<Attribute>>> {
+    attribute().repeated().boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attribute() {
+        let lint = |name: &str| Spanned::any(name.to_string());
+        assert_eq!(
+            attribute().parse_test("#[allow(unused)]"),
+            Ok(Spanned::any(Attribute::Allow(vec![lint("unused")])))
+        );
+        assert_eq!(
+            attribute().parse_test("#[ deny( unused , warnings, ) ]"),
+            Ok(Spanned::any(Attribute::Deny(vec![
+                lint("unused"),
+                lint("warnings")
+            ])))
+        );
+        assert_eq!(
+            attributes().parse_test("#[warn()] #[allow(a)]"),
+            Ok(vec![
+                Spanned::any(Attribute::Warn(vec![])),
+                Spanned::any(Attribute::Allow(vec![lint("a")])),
+            ])
+        );
+        assert!(attribute().parse_test("#[inline]").is_err());
+        assert!(attribute().parse_test("#[allow(unused)").is_err());
+    }
+}
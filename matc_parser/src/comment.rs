@@ -1,4 +1,6 @@
 use crate::prelude::*;
+use matc_ast::Comment;
+use matc_span::Spanned;
 
 fn single_line_comment() -> impl Parser<()> {
     just("//")
@@ -15,6 +17,36 @@ pub(crate) fn comment() -> impl Parser<()> {
     single_line_comment().or(multi_line_comment()).boxed()
 }
 
+/// Collects every comment in the input, skipping string and character literals which may
+/// contain comment-like text.
+pub(crate) fn trivia() -> impl Parser<Vec<Spanned<Comment>>> {
+    let line = just("//")
+        .ignore_then(filter(|c: &char| *c != '\n').repeated())
+        .collect::<String>()
+        .map(|text| Comment::Line(text.trim_end_matches('\r').to_string()));
+    let block = just("/*")
+        .ignore_then(take_until(just("*/")))
+        .map(|(text, _)| Comment::Block(text.into_iter().collect()));
+    let literal = choice((
+        just('"')
+            .then(filter(|c: &char| *c != '"').repeated())
+            .then(just('"'))
+            .ignored(),
+        just('\'').then(any()).then(just('\'')).ignored(),
+    ));
+
+    choice((
+        line.or(block)
+            .map_with_span(|comment, span| Some(Spanned::new(comment, span))),
+        literal.to(None),
+        any().to(None),
+    ))
+    .repeated()
+    .then_ignore(end())
+    .map(|comments| comments.into_iter().flatten().collect())
+    .boxed()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,4 +105,20 @@ mod tests {
         assert!(comment().parse_test("* foo */\n").is_err());
         assert!(comment().parse_test(" foo */\n").is_err());
     }
+
+    #[test]
+    fn test_trivia() {
+        assert_eq!(
+            trivia().parse_test(
+                r#"let a: i32 = 1; // one
+/* two */ let s: i32 = "// not a comment"; '/' /*"*/"#
+            ),
+            Ok(vec![
+                Spanned::any(Comment::Line(" one".to_string())),
+                Spanned::any(Comment::Block(" two ".to_string())),
+                Spanned::any(Comment::Block("\"".to_string())),
+            ])
+        );
+        assert_eq!(trivia().parse_test(""), Ok(vec![]));
+    }
 }
@@ -6,7 +6,11 @@ pub(crate) fn compilation_unit() -> impl Parser<Ast> {
     top_defs()
         .padded()
         .then_ignore(end())
-        .map(|defs| Ast { defs })
+        .map(|defs| Ast {
+            defs,
+            // Collected by `trivia()` separately
+            comments: vec![],
+        })
         .boxed()
 }
 
@@ -45,12 +49,14 @@ fn main() -> i32 {
             Ok(Ast {
                 defs: vec![
                     Spanned::any(Stmt::DefVar {
+                        attrs: vec![],
                         is_mut: false,
                         name: Spanned::any("fuga".to_string()),
                         ty: Spanned::any(Type::I32),
                         expr: Some(Spanned::any(Expr::I32(1))),
                     }),
                     Spanned::any(Stmt::DefFn {
+                        attrs: vec![],
                         name: Spanned::any("f1".to_string()),
                         args: vec![
                             Param {
@@ -76,11 +82,13 @@ fn main() -> i32 {
                         )))])),
                     }),
                     Spanned::any(Stmt::DefFn {
+                        attrs: vec![],
                         name: Spanned::any("main".to_string()),
                         args: vec![],
                         ret_ty: Spanned::any(Type::I32),
                         body: Spanned::any(Stmt::Block(vec![
                             Spanned::any(Stmt::DefVar {
+                                attrs: vec![],
                                 is_mut: true,
                                 name: Spanned::any("hoge".to_string()),
                                 ty: Spanned::any(Type::I32),
@@ -132,6 +140,7 @@ fn main() -> i32 {
                         ])),
                     }),
                 ],
+                comments: vec![],
             })
         );
     }
@@ -1,6 +1,120 @@
 use chumsky::error::Simple;
 use matc_span::Span;
+use std::ops::Deref;
 
 pub use chumsky::error::SimpleReason;
 
-pub type Error = Simple<char, Span>;
+/// Errors on a word rather than on a character
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Unknown {
+    Type,
+    Keyword,
+}
+
+/// A parse error, which may carry a help such as a suggestion for a typo
+#[derive(Debug, PartialEq, Clone)]
+pub struct Error {
+    simple: Box<Simple<char, Span>>,
+    unknown: Option<Unknown>,
+    help: Option<String>,
+}
+
+impl Error {
+    pub fn custom<M: ToString>(span: Span, message: M) -> Self {
+        Self::from(Simple::custom(span, message))
+    }
+
+    pub fn unknown_type(span: Span, name: &str) -> Self {
+        Self {
+            unknown: Some(Unknown::Type),
+            ..Self::custom(span, format!("Unknown type `{}`", name))
+        }
+    }
+
+    /// `word` is not but looks like a keyword.
+    pub fn unknown_keyword(span: Span, word: &str) -> Self {
+        Self {
+            unknown: Some(Unknown::Keyword),
+            ..Self::custom(span, format!("Unexpected identifier `{}`", word))
+        }
+    }
+
+    pub fn unknown(&self) -> Option<Unknown> {
+        self.unknown
+    }
+
+    pub fn with_help<H: ToString>(mut self, help: H) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    pub fn help(&self) -> Option<&str> {
+        self.help.as_deref()
+    }
+}
+
+impl From<Simple<char, Span>> for Error {
+    fn from(simple: Simple<char, Span>) -> Self {
+        Self {
+            simple: Box::new(simple),
+            unknown: None,
+            help: None,
+        }
+    }
+}
+
+impl Deref for Error {
+    type Target = Simple<char, Span>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.simple
+    }
+}
+
+impl chumsky::Error<char> for Error {
+    type Span = Span;
+    type Label = &'static str;
+
+    fn expected_input_found<Iter: IntoIterator<Item = Option<char>>>(
+        span: Span,
+        expected: Iter,
+        found: Option<char>,
+    ) -> Self {
+        Self::from(Simple::expected_input_found(span, expected, found))
+    }
+
+    fn unclosed_delimiter(
+        unclosed_span: Span,
+        unclosed: char,
+        span: Span,
+        expected: char,
+        found: Option<char>,
+    ) -> Self {
+        Self::from(Simple::unclosed_delimiter(
+            unclosed_span,
+            unclosed,
+            span,
+            expected,
+            found,
+        ))
+    }
+
+    fn with_label(self, label: Self::Label) -> Self {
+        Self {
+            simple: Box::new(self.simple.with_label(label)),
+            ..self
+        }
+    }
+
+    /// Prefers the error with a help since it knows better what went wrong.
+    fn merge(self, other: Self) -> Self {
+        let (first, second) = match (&self.help, &other.help) {
+            (None, Some(_)) => (other, self),
+            _ => (self, other),
+        };
+        Self {
+            simple: Box::new(first.simple.merge(*second.simple)),
+            ..first
+        }
+    }
+}
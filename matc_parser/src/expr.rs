@@ -6,13 +6,17 @@ use crate::variable::variable;
 use matc_ast::Expr;
 use matc_span::Spanned;
 
-pub(crate) fn args(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Vec<Spanned<Expr>>> + '_ {
+pub(crate) fn args(
+    fn_call: Option<Rec<'_, Spanned<Expr>>>,
+) -> impl Parser<Vec<Spanned<Expr>>> + '_ {
     expr(fn_call).separated_by(just(',')).boxed()
 }
 
-pub(crate) fn expr(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
-    expr8(fn_call.clone())
-        .then(just("||").to(Expr::Or).then(expr8(fn_call)).repeated())
+pub(crate) fn expr(fn_call: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+    let expr8 = expr8(fn_call);
+    expr8
+        .clone()
+        .then(just("||").to(Expr::Or).then(expr8).repeated())
         .foldl(|lhs, (op, rhs)| {
             let span = lhs.span.union(rhs.span);
             Spanned::new(op(lhs, rhs), span)
@@ -20,9 +24,11 @@ pub(crate) fn expr(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<E
         .boxed()
 }
 
-fn expr8(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
-    expr7(fn_call.clone())
-        .then(just("&&").to(Expr::And).then(expr7(fn_call)).repeated())
+fn expr8(fn_call: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+    let expr7 = expr7(fn_call);
+    expr7
+        .clone()
+        .then(just("&&").to(Expr::And).then(expr7).repeated())
         .foldl(|lhs, (op, rhs)| {
             let span = lhs.span.union(rhs.span);
             Spanned::new(op(lhs, rhs), span)
@@ -30,8 +36,10 @@ fn expr8(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_
         .boxed()
 }
 
-fn expr7(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
-    expr6(fn_call.clone())
+fn expr7(fn_call: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+    let expr6 = expr6(fn_call);
+    expr6
+        .clone()
         .then(
             choice((
                 just("!=").to(Expr::Neq as fn(_, _) -> _),
@@ -41,7 +49,7 @@ fn expr7(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_
                 just('>').to(Expr::Gt as fn(_, _) -> _),
                 just('<').to(Expr::Lt as fn(_, _) -> _),
             ))
-            .then(expr6(fn_call))
+            .then(expr6)
             .repeated(),
         )
         .foldl(|lhs, (op, rhs)| {
@@ -51,9 +59,11 @@ fn expr7(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_
         .boxed()
 }
 
-fn expr6(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
-    expr5(fn_call.clone())
-        .then(just('|').to(Expr::BitOr).then(expr5(fn_call)).repeated())
+fn expr6(fn_call: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+    let expr5 = expr5(fn_call);
+    expr5
+        .clone()
+        .then(just('|').to(Expr::BitOr).then(expr5).repeated())
         .foldl(|lhs, (op, rhs)| {
             let span = lhs.span.union(rhs.span);
             Spanned::new(op(lhs, rhs), span)
@@ -61,9 +71,11 @@ fn expr6(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_
         .boxed()
 }
 
-fn expr5(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
-    expr4(fn_call.clone())
-        .then(just('^').to(Expr::BitXor).then(expr4(fn_call)).repeated())
+fn expr5(fn_call: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+    let expr4 = expr4(fn_call);
+    expr4
+        .clone()
+        .then(just('^').to(Expr::BitXor).then(expr4).repeated())
         .foldl(|lhs, (op, rhs)| {
             let span = lhs.span.union(rhs.span);
             Spanned::new(op(lhs, rhs), span)
@@ -71,9 +83,11 @@ fn expr5(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_
         .boxed()
 }
 
-fn expr4(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
-    expr3(fn_call.clone())
-        .then(just('&').to(Expr::BitAnd).then(expr3(fn_call)).repeated())
+fn expr4(fn_call: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+    let expr3 = expr3(fn_call);
+    expr3
+        .clone()
+        .then(just('&').to(Expr::BitAnd).then(expr3).repeated())
         .foldl(|lhs, (op, rhs)| {
             let span = lhs.span.union(rhs.span);
             Spanned::new(op(lhs, rhs), span)
@@ -81,14 +95,16 @@ fn expr4(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_
         .boxed()
 }
 
-fn expr3(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
-    expr2(fn_call.clone())
+fn expr3(fn_call: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+    let expr2 = expr2(fn_call);
+    expr2
+        .clone()
         .then(
             choice((
                 just("<<").to(Expr::Shl as fn(_, _) -> _),
                 just(">>").to(Expr::Shr as fn(_, _) -> _),
             ))
-            .then(expr2(fn_call))
+            .then(expr2)
             .repeated(),
         )
         .foldl(|lhs, (op, rhs)| {
@@ -98,14 +114,16 @@ fn expr3(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_
         .boxed()
 }
 
-fn expr2(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
-    expr1(fn_call.clone())
+fn expr2(fn_call: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+    let expr1 = expr1(fn_call);
+    expr1
+        .clone()
         .then(
             choice((
                 just('+').to(Expr::Add as fn(_, _) -> _),
                 just('-').to(Expr::Sub as fn(_, _) -> _),
             ))
-            .then(expr1(fn_call))
+            .then(expr1)
             .repeated(),
         )
         .foldl(|lhs, (op, rhs)| {
@@ -115,15 +133,16 @@ fn expr2(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_
         .boxed()
 }
 
-fn expr1(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
-    cast(fn_call.clone())
+fn expr1(fn_call: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+    let cast = cast(fn_call);
+    cast.clone()
         .then(
             choice((
                 just('*').to(Expr::Mul as fn(_, _) -> _),
                 just('/').to(Expr::Div as fn(_, _) -> _),
                 just('%').to(Expr::Rem as fn(_, _) -> _),
             ))
-            .then(cast(fn_call))
+            .then(cast)
             .repeated(),
         )
         .foldl(|lhs, (op, rhs)| {
@@ -134,7 +153,7 @@ fn expr1(fn_call: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_
 }
 
 // cast expr: expr as type as type
-pub(crate) fn cast(fn_call_rec: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+pub(crate) fn cast(fn_call_rec: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
     let as_expr = just("as").to(Expr::As).then(typeref().padded()).repeated();
 
     match fn_call_rec {
@@ -158,34 +177,40 @@ pub(crate) fn cast(fn_call_rec: Option<Rec<Spanned<Expr>>>) -> impl Parser<Spann
 // fn(a1, a2)
 fn fn_call() -> impl Parser<Spanned<Expr>> {
     recursive(|fn_call| {
-        primary()
+        primary(Some(fn_call.clone()))
             .then(
                 args(Some(fn_call))
                     .delimited_by(just('('), just(')'))
+                    .recover_with(nested_delimiters('(', ')', [('{', '}')], |span| {
+                        vec![Spanned::new(Expr::Error, span)]
+                    }))
+                    .map_with_span(|args, span| (args, span))
+                    .padded()
                     .repeated(),
             )
-            .foldl(|name, args| {
-                let span = if let Some(first) = args.first() {
-                    // Merge args spans into one span
-                    name.span.union(
-                        args.iter()
-                            .map(|f| f.span)
-                            .fold(first.span, |acc, xs| acc.union(xs)),
-                    )
-                } else {
-                    // No args found
-                    name.span
-                };
+            .foldl(|name, (args, parens)| {
+                // Cover the callee up to the closing parenthesis
+                let span = name.span.union(parens);
                 Spanned::new(Expr::FnCall { name, args }, span)
             })
     })
     .boxed()
 }
 
-fn primary() -> impl Parser<Spanned<Expr>> {
-    choice((integer(), character(), string(), variable()))
-        .padded()
-        .boxed()
+fn primary(fn_call: Option<Rec<'_, Spanned<Expr>>>) -> impl Parser<Spanned<Expr>> + '_ {
+    choice((
+        integer(),
+        character(),
+        string(),
+        variable(),
+        expr(fn_call)
+            .delimited_by(just('('), just(')'))
+            .recover_with(nested_delimiters('(', ')', [('{', '}')], |span| {
+                Spanned::new(Expr::Error, span)
+            })),
+    ))
+    .padded()
+    .boxed()
 }
 
 #[cfg(test)]
@@ -212,6 +237,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_paren() {
+        assert_eq!(
+            expr(None).parse_test("(1 + 2) * f((3))"),
+            Ok(Spanned::any(Expr::Mul(
+                Spanned::any(Expr::Add(
+                    Spanned::any(Expr::I32(1)),
+                    Spanned::any(Expr::I32(2)),
+                )),
+                Spanned::any(Expr::FnCall {
+                    name: Spanned::any(Expr::Variable("f".to_string())),
+                    args: vec![Spanned::any(Expr::I32(3))],
+                }),
+            )))
+        );
+        assert!(expr(None).parse_test("(1 + 2").is_err());
+
+        let (output, errors) = expr(None).parse_recovery_test("(1 +) * f(2, *)");
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            output,
+            Some(Spanned::any(Expr::Mul(
+                Spanned::any(Expr::Error),
+                Spanned::any(Expr::FnCall {
+                    name: Spanned::any(Expr::Variable("f".to_string())),
+                    args: vec![Spanned::any(Expr::Error)],
+                }),
+            )))
+        );
+    }
+
     #[test]
     fn test_expr() {
         assert_eq!(
@@ -610,14 +666,20 @@ mod tests {
 
     #[test]
     fn test_primary() {
-        assert_eq!(primary().parse_test("1"), Ok(Spanned::any(Expr::I32(1))));
-        assert_eq!(primary().parse_test("'a'"), Ok(Spanned::any(Expr::I8(97))));
         assert_eq!(
-            primary().parse_test("\"a\""),
+            primary(None).parse_test("1"),
+            Ok(Spanned::any(Expr::I32(1)))
+        );
+        assert_eq!(
+            primary(None).parse_test("'a'"),
+            Ok(Spanned::any(Expr::I8(97)))
+        );
+        assert_eq!(
+            primary(None).parse_test("\"a\""),
             Ok(Spanned::any(Expr::String("a".to_string())))
         );
         assert_eq!(
-            primary().parse_test("var"),
+            primary(None).parse_test("var"),
             Ok(Spanned::any(Expr::Variable("var".to_string())))
         );
     }
@@ -1,9 +1,66 @@
 use crate::comment::comment;
+use crate::error::Error;
 use crate::prelude::*;
+use unicode_ident::{is_xid_continue, is_xid_start};
+use unicode_normalization::UnicodeNormalization;
 
 pub(crate) fn ident() -> impl Parser<String> {
-    text::ident()
+    raw_ident()
         .padded()
         .padded_by(comment().padded().repeated())
         .boxed()
 }
+
+/// `XID_Start` or `_` followed by `XID_Continue`s, normalized to NFC
+pub(crate) fn raw_ident() -> impl Parser<String> {
+    filter(|c: &char| is_xid_start(*c) || *c == '_')
+        .chain(filter(|c: &char| is_xid_continue(*c)).repeated())
+        .collect::<String>()
+        .map(|name| name.nfc().collect())
+        .boxed()
+}
+
+/// Like `text::keyword` but on Unicode identifiers, so that `ifé` is not `if` followed by `é`
+pub(crate) fn keyword(keyword: &'static str) -> impl Parser<()> {
+    raw_ident()
+        .try_map(move |name, span| {
+            if name == keyword {
+                Ok(())
+            } else {
+                Err(Error::expected_input_found(span, None, None))
+            }
+        })
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ident() {
+        assert_eq!(ident().parse_test("_a1"), Ok("_a1".to_string()));
+        assert_eq!(ident().parse_test("変数"), Ok("変数".to_string()));
+        assert_eq!(ident().parse_test("λ_2"), Ok("λ_2".to_string()));
+        // e + COMBINING ACUTE ACCENT
+        assert_eq!(
+            ident().parse_test("cafe\u{301}"),
+            Ok("caf\u{e9}".to_string())
+        );
+        // ᄒ + ᅡ + ᆫ
+        assert_eq!(
+            ident().parse_test("\u{1112}\u{1161}\u{11ab}"),
+            Ok("\u{d55c}".to_string())
+        );
+        assert!(ident().parse_test("1a").is_err());
+        assert!(ident().parse_test("\u{301}").is_err());
+        assert!(ident().then_ignore(end()).parse_test("a😀").is_err());
+    }
+
+    #[test]
+    fn test_keyword() {
+        assert_eq!(keyword("if").parse_test("if"), Ok(()));
+        assert!(keyword("if").parse_test("ifé").is_err());
+        assert!(keyword("if").parse_test("iff").is_err());
+    }
+}
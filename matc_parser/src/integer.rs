@@ -1,5 +1,6 @@
 use crate::error::Error;
 use crate::prelude::*;
+use crate::string::escape;
 use matc_ast::Expr;
 use matc_span::Spanned;
 
@@ -14,16 +15,28 @@ pub(crate) fn integer() -> impl Parser<Spanned<Expr>> {
                 // No suffix
                 _ => num.parse().map(Expr::I32),
             }
-            .map_err(|e| Simple::custom(span, format!("{}", e)))
+            .map_err(|e| Error::custom(span, e))
         })
         .map_with_span(Spanned::new)
         .boxed()
 }
 
 pub(crate) fn character() -> impl Parser<Spanned<Expr>> {
-    filter(|c: &char| c.is_ascii())
+    filter(|c: &char| *c != '\'' && *c != '\\')
+        .or(escape())
         .delimited_by(just('\''), just('\''))
-        .map(|c| Expr::I8(c as i8))
+        .try_map(|c, span| {
+            i8::try_from(c as u32).map(Expr::I8).map_err(|_| {
+                Error::custom(
+                    span,
+                    format!(
+                        "Character literal `{}` does not fit in `char`",
+                        c.escape_debug()
+                    ),
+                )
+                .with_help("`char` holds an ASCII character; use a string literal for other text")
+            })
+        })
         .map_with_span(Spanned::new)
         .boxed()
 }
@@ -31,6 +44,7 @@ pub(crate) fn character() -> impl Parser<Spanned<Expr>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::SimpleReason;
 
     #[test]
     fn test_integer() {
@@ -71,5 +85,21 @@ mod tests {
         assert!(character().parse_test("a").is_err());
         assert!(character().parse_test("'aa'").is_err());
         assert!(character().parse_test("'\nn'").is_err());
+
+        assert_eq!(
+            character().parse_test(r"'\''"),
+            Ok(Spanned::any(Expr::I8(39)))
+        );
+        assert_eq!(
+            character().parse_test(r"'\u{7f}'"),
+            Ok(Spanned::any(Expr::I8(127)))
+        );
+        for code in ["'é'", "'あ'", r"'\u{80}'"] {
+            let errors = character().parse_test(code).unwrap_err();
+            assert!(
+                matches!(errors[0].reason(), SimpleReason::Custom(msg) if msg.contains("does not fit"))
+            );
+            assert!(errors[0].help().is_some());
+        }
     }
 }
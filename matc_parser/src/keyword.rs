@@ -0,0 +1,56 @@
+use crate::error::Error;
+use crate::ident::raw_ident;
+use crate::prelude::*;
+use crate::suggest::closest;
+
+pub(crate) const TOP_KEYWORDS: &[&str] = &["fn", "let"];
+pub(crate) const STMT_KEYWORDS: &[&str] = &["let", "if", "return"];
+
+/// Fails on a word which looks like a typo of one of `keywords`, suggesting the keyword.
+/// Otherwise fails without consuming any input so that other errors take precedence.
+pub(crate) fn misspelled_keyword<T: Clone>(keywords: &'static [&'static str]) -> impl Parser<T> {
+    raw_ident()
+        .rewind()
+        .try_map(move |word: String, span| {
+            closest(&word, keywords.iter().copied())
+                .ok_or_else(|| Error::expected_input_found(span, None, None))
+        })
+        // Consume the word to report at the same position as errors after it, e.g. `x` in
+        // `retrun x;`.
+        .then(
+            raw_ident()
+                .map_with_span(|word, span| (word, span))
+                .padded(),
+        )
+        .try_map(|(keyword, (word, span)), _| {
+            Err(Error::unknown_keyword(span, &word).with_help(format!(
+                "there is a keyword `{}` with a similar name",
+                keyword
+            )))
+        })
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Unknown;
+
+    #[test]
+    fn test_misspelled_keyword() {
+        let errors = misspelled_keyword::<()>(STMT_KEYWORDS)
+            .parse_test("retrun x")
+            .unwrap_err();
+        assert_eq!(errors[0].unknown(), Some(Unknown::Keyword));
+        assert_eq!(errors[0].span().range(), 0..6);
+        assert_eq!(
+            errors[0].help(),
+            Some("there is a keyword `return` with a similar name")
+        );
+
+        let errors = misspelled_keyword::<()>(STMT_KEYWORDS)
+            .parse_test("foo")
+            .unwrap_err();
+        assert_eq!(errors[0].unknown(), None);
+    }
+}
@@ -1,36 +1,95 @@
+mod attribute;
 mod comment;
 mod compilation_unit;
 pub mod error;
 mod expr;
 mod ident;
 mod integer;
+mod keyword;
+mod line;
 pub(crate) mod prelude;
 mod stmt;
 mod string;
+pub mod suggest;
 mod ty;
 mod variable;
 
-use chumsky::{Parser, Span, Stream};
+use chumsky::{Span, Stream};
+use comment::trivia;
 use compilation_unit::compilation_unit;
 use error::Error;
-use matc_ast::Ast;
+use line::line;
+use matc_ast::{Ast, Line};
 use matc_span::SrcId;
 use std::path::Path;
 
 pub fn parse<P: AsRef<Path>>(src: P, code: &str) -> Result<Ast, Vec<Error>> {
+    match parse_recovery(src, code) {
+        (Some(ast), errors) if errors.is_empty() => Ok(ast),
+        (_, errors) => Err(errors),
+    }
+}
+
+/// Parses `code` even if it has syntax errors, where the broken parts are replaced with error
+/// nodes such as `Stmt::Error`, so that the rest can still be analyzed.
+pub fn parse_recovery<P: AsRef<Path>>(src: P, code: &str) -> (Option<Ast>, Vec<Error>) {
+    let (ast, mut errors) = parse_recovery_with(compilation_unit(), &src, code, 0);
+    let (comments, comment_errors) = parse_recovery_with(trivia(), &src, code, 0);
+    errors.extend(comment_errors);
+    let ast = ast.map(|ast| Ast {
+        comments: comments.unwrap_or_default(),
+        ..ast
+    });
+    (ast, errors)
+}
+
+/// Parses an input of the interactive mode, where `offset` is the byte position of `code` within
+/// the whole session so that spans can point to previous inputs as well.
+pub fn parse_line<P: AsRef<Path>>(src: P, code: &str, offset: usize) -> Result<Line, Vec<Error>> {
+    match parse_recovery_with(line(), src, code, offset) {
+        (Some(output), errors) if errors.is_empty() => Ok(output),
+        (_, errors) => Err(errors),
+    }
+}
+
+fn parse_recovery_with<T, P: AsRef<Path>>(
+    parser: impl prelude::Parser<T>,
+    src: P,
+    code: &str,
+    offset: usize,
+) -> (Option<T>, Vec<Error>) {
     let src = SrcId::from_path(src);
-    let len = code.chars().count();
-    let span = |i| Span::new(src, i..i + 1);
-    let eoi = span(len);
+    let span = |i, len| Span::new(src, offset + i..offset + i + len);
+    // Empty so that diagnostics at the end of input stay within the source.
+    let eoi = span(code.len(), 0);
 
-    let (ast, errors) = compilation_unit().parse_recovery(Stream::from_iter(
+    parser.parse_recovery(Stream::from_iter(
         eoi,
-        code.chars().enumerate().map(|(i, c)| (c, span(i))),
-    ));
+        code.char_indices().map(|(i, c)| (c, span(i, c.len_utf8()))),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_spans() {
+        let code = "/* é😀 */ let x: i32 = 1; // \"日本\"\nlet y: i32 = 2;";
+        let ast = parse("test.mat", code).unwrap();
+        let text = |span: matc_span::Span| &code[span.range()];
+        assert_eq!(text(ast.comments[0].span), "/* é😀 */");
+        assert_eq!(text(ast.comments[1].span), "// \"日本\"");
+        assert_eq!(text(ast.defs[0].span), "let x: i32 = 1;");
+        assert_eq!(text(ast.defs[1].span), "let y: i32 = 2;");
+        assert_eq!(ast.defs[1].span.range(), 41..56);
+    }
 
-    if let Some(ast) = ast {
-        Ok(ast)
-    } else {
-        Err(errors)
+    #[test]
+    fn test_byte_spans_in_errors() {
+        let code = "// ü\nlet c: char = 'é';";
+        let errors = parse("test.mat", code).unwrap_err();
+        assert_eq!(errors[0].span().range(), 20..24);
+        assert_eq!(&code[errors[0].span().range()], "'é'");
     }
 }
@@ -0,0 +1,63 @@
+use crate::expr::expr;
+use crate::prelude::*;
+use crate::stmt::top_def;
+use matc_ast::Line;
+
+pub(crate) fn line() -> impl Parser<Line> {
+    // Check the end of input in each branch so that the furthest error is reported,
+    // e.g. an unclosed function rather than `fn` as an expression.
+    choice((
+        top_def()
+            .chain(top_def().repeated())
+            .map(Line::Defs)
+            .padded()
+            .then_ignore(end()),
+        expr(None)
+            .then_ignore(just(';').or_not())
+            .map(Line::Expr)
+            .padded()
+            .then_ignore(end()),
+    ))
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matc_ast::{Expr, Stmt, Type};
+    use matc_span::Spanned;
+
+    #[test]
+    fn test_line() {
+        assert_eq!(
+            line().parse_test("let foo: i32 = 1;"),
+            Ok(Line::Defs(vec![Spanned::any(Stmt::DefVar {
+                attrs: vec![],
+                is_mut: false,
+                name: Spanned::any("foo".to_string()),
+                ty: Spanned::any(Type::I32),
+                expr: Some(Spanned::any(Expr::I32(1))),
+            })]))
+        );
+        assert_eq!(
+            line().parse_test(" foo + 1 "),
+            Ok(Line::Expr(Spanned::any(Expr::Add(
+                Spanned::any(Expr::Variable("foo".to_string())),
+                Spanned::any(Expr::I32(1)),
+            ))))
+        );
+        assert_eq!(
+            line().parse_test("f(1);"),
+            Ok(Line::Expr(Spanned::any(Expr::FnCall {
+                name: Spanned::any(Expr::Variable("f".to_string())),
+                args: vec![Spanned::any(Expr::I32(1))],
+            })))
+        );
+        assert!(line().parse_test("").is_err());
+        assert!(line().parse_test("return 1;").is_err());
+        assert!(matches!(
+            line().parse_test("fn f() -> i32 {").unwrap_err()[..],
+            [ref err] if err.found().is_none()
+        ));
+    }
+}
@@ -7,14 +7,23 @@ use crate::error::Error;
 pub(crate) trait Parser<T>: chumsky::Parser<char, T, Error = Error> + Clone {
     #[cfg(test)]
     fn parse_test(&self, stream: &str) -> Result<T, Vec<Self::Error>> {
+        match self.parse_recovery_test(stream) {
+            (Some(output), errors) if errors.is_empty() => Ok(output),
+            (_, errors) => Err(errors),
+        }
+    }
+
+    #[cfg(test)]
+    fn parse_recovery_test(&self, stream: &str) -> (Option<T>, Vec<Self::Error>) {
         use matc_span::{Span, SrcId};
 
-        let len = stream.chars().count();
-        let span = |i| Span::new(SrcId::any(), i..i + 1);
+        let span = |i, len| Span::new(SrcId::any(), i..i + len);
 
-        self.parse(chumsky::Stream::from_iter(
-            span(len),
-            stream.chars().enumerate().map(|(i, c)| (c, span(i))),
+        self.parse_recovery(chumsky::Stream::from_iter(
+            span(stream.len(), 0),
+            stream
+                .char_indices()
+                .map(|(i, c)| (c, span(i, c.len_utf8()))),
         ))
     }
 }
@@ -1,18 +1,63 @@
+use crate::attribute::attributes;
 use crate::comment::comment;
+use crate::error::Error;
 use crate::expr::{cast, expr};
-use crate::ident::ident;
+use crate::ident::{ident, keyword};
+use crate::keyword::{misspelled_keyword, STMT_KEYWORDS, TOP_KEYWORDS};
 use crate::prelude::*;
 use crate::ty::typeref;
-use matc_ast::{Param, Stmt};
-use matc_span::Spanned;
+use matc_ast::{Expr, Param, Stmt};
+use matc_span::{Span, Spanned};
 
 pub(crate) fn top_defs() -> impl Parser<Vec<Spanned<Stmt>>> {
-    choice((defvar(), defn())).repeated().boxed()
+    let comments = comment().padded().repeated();
+    comments
+        .clone()
+        .ignore_then(top_item().then_ignore(comments).repeated())
+        .map(|defs| defs.into_iter().flatten().collect())
+        .boxed()
+}
+
+// A definition, or nothing when a broken one is skipped up to the next definition.
+fn top_item() -> impl Parser<Option<Spanned<Stmt>>> {
+    // Blocks are skipped as a whole so that their statements are not taken for definitions.
+    let skipped_block = recursive(|block| {
+        just('{')
+            .ignore_then(none_of("{}").ignored().or(block).repeated())
+            .then_ignore(just('}'))
+            .ignored()
+    });
+
+    text::whitespace()
+        .ignore_then(any().rewind())
+        .ignore_then(
+            top_def()
+                .map(Ok)
+                .or(skipped_block.map_with_span(|_, span| Err(span)))
+                .recover_with(skip_then_retry_until([]))
+                .recover_with(skip_until([], |span| Ok(Spanned::new(Stmt::Error, span)))),
+        )
+        .validate(|def, span: Span, emit| match def {
+            Ok(def) => Some(def),
+            Err(block) => {
+                // Not skipped in recovery but found in place of a definition
+                if block.range().start == span.range().start {
+                    let open = Span::new(block.src(), block.range().start..block.range().start + 1);
+                    emit(Error::expected_input_found(open, None, Some('{')));
+                }
+                None
+            }
+        })
+        .boxed()
+}
+
+pub(crate) fn top_def() -> impl Parser<Spanned<Stmt>> {
+    choice((defvar(), defn(), misspelled_keyword(TOP_KEYWORDS))).boxed()
 }
 
 // name1: type1
 fn param() -> impl Parser<Param> {
-    text::keyword("mut")
+    keyword("mut")
         .or_not()
         .padded()
         .then(ident().map_with_span(Spanned::new))
@@ -28,9 +73,8 @@ fn param() -> impl Parser<Param> {
 
 // fn name(...) -> type {}
 fn defn() -> impl Parser<Spanned<Stmt>> {
-    text::keyword("fn")
-        .padded()
-        .ignore_then(ident().map_with_span(Spanned::new))
+    let signature = ident()
+        .map_with_span(Spanned::new)
         .then(
             param()
                 .padded()
@@ -39,11 +83,24 @@ fn defn() -> impl Parser<Spanned<Stmt>> {
         )
         .padded()
         .then_ignore(just("->"))
-        .then(typeref().padded())
+        .then(typeref().padded());
+
+    attributes()
+        .then_ignore(keyword("fn").padded())
+        // Still parse the body of a broken signature to report errors in it.
+        .then(
+            signature
+                .map(Some)
+                .recover_with(skip_until(['{'], |_| None)),
+        )
         .then(block(None))
-        .map_with_span(|(((name, args), ret_ty), body), span| {
+        .map_with_span(|((attrs, signature), body), span| {
+            let Some(((name, args), ret_ty)) = signature else {
+                return Spanned::new(Stmt::Error, span);
+            };
             Spanned::new(
                 Stmt::DefFn {
+                    attrs,
                     name,
                     args,
                     ret_ty,
@@ -57,17 +114,28 @@ fn defn() -> impl Parser<Spanned<Stmt>> {
 
 // let mut var: type = expr;
 fn defvar() -> impl Parser<Spanned<Stmt>> {
-    text::keyword("let")
-        .padded()
-        .ignore_then(just("mut").or_not())
+    attributes()
+        .then_ignore(keyword("let").padded())
+        .then(just("mut").or_not())
         .then(ident().map_with_span(Spanned::new))
         .then_ignore(just(':'))
         .then(typeref().padded())
-        .then(just('=').ignore_then(expr(None)).or_not())
-        .then_ignore(just(';'))
-        .map_with_span(|(((mt, nm), ty), expr), span| {
+        .then(
+            just('=')
+                .ignore_then(expr(None))
+                .or_not()
+                .then_ignore(just(';').rewind())
+                // Keep the variable defined for the rest of the code. Also stop at the end of
+                // the line in case of a missing `;`.
+                .recover_with(skip_until([';', '}', '\n'], |span| {
+                    Some(Spanned::new(Expr::Error, span))
+                })),
+        )
+        .then_ignore(just(';').or_not())
+        .map_with_span(|((((attrs, mt), nm), ty), expr), span| {
             Spanned::new(
                 Stmt::DefVar {
+                    attrs,
                     is_mut: mt.is_some(),
                     name: nm,
                     ty,
@@ -87,15 +155,46 @@ fn defvar() -> impl Parser<Spanned<Stmt>> {
 //     ...
 // }
 
-fn block(if_stmt: Option<Rec<Spanned<Stmt>>>) -> impl Parser<Spanned<Stmt>> + '_ {
+fn block(if_stmt: Option<Rec<'_, Spanned<Stmt>>>) -> impl Parser<Spanned<Stmt>> + '_ {
     recursive(|block| {
-        defvar()
-            .or(stmt(Some(block), if_stmt))
-            .padded_by(comment().padded().repeated()) // TODO: implement lexer to simplify comment treatments?
-            .repeated()
-            .padded()
-            .delimited_by(just('{'), just('}'))
-            .map_with_span(|block, span| Spanned::new(Stmt::Block(block), span))
+        just('{')
+            .map_with_span(|_, span| span)
+            .then(
+                // TODO: implement lexer to simplify comment treatments?
+                comment()
+                    .padded()
+                    .repeated()
+                    .ignore_then(
+                        text::whitespace()
+                            .ignore_then(none_of('}').rewind())
+                            .ignore_then(
+                                defvar()
+                                    .or(stmt(Some(block), if_stmt))
+                                    .or(misspelled_keyword(STMT_KEYWORDS))
+                                    // Skip to the end of a broken statement, or of the block.
+                                    .recover_with(skip_until([';', '}'], |span| {
+                                        Spanned::new(Stmt::Error, span)
+                                    })),
+                            )
+                            .padded()
+                            .then_ignore(comment().padded().repeated())
+                            .repeated(),
+                    )
+                    .padded(),
+            )
+            // Distinguish unclosed braces from other errors, e.g. to wait for more input.
+            .then(
+                just('}')
+                    .to(None)
+                    .or(end().map_with_span(|_, span| Some(span))),
+            )
+            // Keep the statements so far even if unclosed.
+            .validate(|((open, block), eoi), span, emit| {
+                if let Some(eoi) = eoi {
+                    emit(Error::unclosed_delimiter(open, '{', eoi, '}', None));
+                }
+                Spanned::new(Stmt::Block(block), span)
+            })
             .boxed()
     })
 }
@@ -136,12 +235,12 @@ fn stmt<'a>(
 // }
 fn if_stmt() -> impl Parser<Spanned<Stmt>> {
     recursive(|if_stmt| {
-        text::keyword("if")
+        keyword("if")
             .padded()
             .ignore_then(expr(None))
             .then(block(Some(if_stmt.clone())))
             .then(
-                text::keyword("else")
+                keyword("else")
                     .padded()
                     .ignore_then(block(Some(if_stmt.clone())).or(if_stmt))
                     .or_not(),
@@ -154,7 +253,7 @@ fn if_stmt() -> impl Parser<Spanned<Stmt>> {
 }
 
 fn return_stmt() -> impl Parser<Spanned<Stmt>> {
-    text::keyword("return")
+    keyword("return")
         .padded()
         .ignore_then(expr(None).or_not())
         .map(Stmt::Return)
@@ -194,11 +293,23 @@ fn assign_stmt() -> impl Parser<Spanned<Stmt>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use matc_ast::{Expr, Type};
+    use crate::error::SimpleReason;
+    use matc_ast::{Attribute, FnType, Type};
 
     #[test]
     fn test_top_defs() {
         assert_eq!(top_defs().parse_test(""), Ok(vec![]));
+        assert_eq!(top_defs().parse_test("// comment"), Ok(vec![]));
+        assert_eq!(
+            top_defs().parse_test("/* a */ fn f() -> void {} // b\n// c"),
+            Ok(vec![Spanned::any(Stmt::DefFn {
+                attrs: vec![],
+                name: Spanned::any("f".to_string()),
+                args: vec![],
+                ret_ty: Spanned::any(Type::Void),
+                body: Spanned::any(Stmt::Block(vec![])),
+            })])
+        );
         assert_eq!(
             top_defs().parse_test(
                 r#"
@@ -209,12 +320,14 @@ mod tests {
             ),
             Ok(vec![
                 Spanned::any(Stmt::DefVar {
+                    attrs: vec![],
                     is_mut: false,
                     name: Spanned::any("foo".to_string()),
                     ty: Spanned::any(Type::I8),
                     expr: Some(Spanned::any(Expr::I32(1))),
                 }),
                 Spanned::any(Stmt::DefFn {
+                    attrs: vec![],
                     name: Spanned::any("f1".to_string()),
                     args: vec![],
                     ret_ty: Spanned::any(Type::I32),
@@ -224,6 +337,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_top_defs_recovery() {
+        let (defs, errors) = top_defs().parse_recovery_test(
+            r#"
+            let x: i32 = 1
+            } fn f() -> void {}
+            fnn g() -> void { let y: i32 = 1; }
+            fn h( -> void { return; }
+            let z: i32 = 2;
+            "#,
+        );
+        assert_eq!(errors.len(), 4);
+        assert_eq!(
+            defs,
+            Some(vec![
+                Spanned::any(Stmt::DefVar {
+                    attrs: vec![],
+                    is_mut: false,
+                    name: Spanned::any("x".to_string()),
+                    ty: Spanned::any(Type::I32),
+                    expr: Some(Spanned::any(Expr::Error)),
+                }),
+                Spanned::any(Stmt::DefFn {
+                    attrs: vec![],
+                    name: Spanned::any("f".to_string()),
+                    args: vec![],
+                    ret_ty: Spanned::any(Type::Void),
+                    body: Spanned::any(Stmt::Block(vec![])),
+                }),
+                // `y` is not taken for a toplevel definition.
+                Spanned::any(Stmt::Error),
+                Spanned::any(Stmt::DefVar {
+                    attrs: vec![],
+                    is_mut: false,
+                    name: Spanned::any("z".to_string()),
+                    ty: Spanned::any(Type::I32),
+                    expr: Some(Spanned::any(Expr::I32(2))),
+                }),
+            ])
+        );
+
+        let (defs, errors) = top_defs().parse_recovery_test("{ let x: i32; }");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(defs, Some(vec![]));
+    }
+
     #[test]
     fn test_param() {
         assert_eq!(
@@ -249,6 +408,7 @@ mod tests {
         assert_eq!(
             defn().parse_test("fn name() -> i32 {}"),
             Ok(Spanned::any(Stmt::DefFn {
+                attrs: vec![],
                 name: Spanned::any("name".to_string()),
                 args: vec![],
                 ret_ty: Spanned::any(Type::I32),
@@ -258,6 +418,7 @@ mod tests {
         assert_eq!(
             defn().parse_test("fn name(a1: char) -> i32 {}"),
             Ok(Spanned::any(Stmt::DefFn {
+                attrs: vec![],
                 name: Spanned::any("name".to_string()),
                 args: vec![Param {
                     is_mut: false,
@@ -269,14 +430,74 @@ mod tests {
             }))
         );
 
+        assert_eq!(
+            defn().parse_test("#[allow(unused)] fn name() -> i32 {}"),
+            Ok(Spanned::any(Stmt::DefFn {
+                attrs: vec![Spanned::any(Attribute::Allow(vec![Spanned::any(
+                    "unused".to_string()
+                )]))],
+                name: Spanned::any("name".to_string()),
+                args: vec![],
+                ret_ty: Spanned::any(Type::I32),
+                body: Spanned::any(Stmt::Block(vec![])),
+            }))
+        );
+
         assert!(defn().parse_test("fn name(): i16 {}").is_err());
     }
 
+    #[test]
+    fn test_defn_unknown_types() {
+        let (def, errors) =
+            defn().parse_recovery_test("fn f(a: foo, b: i32, c: fn(bar) -> i32) -> baz {}");
+        let messages: Vec<_> = errors
+            .iter()
+            .map(|err| match err.reason() {
+                SimpleReason::Custom(msg) => msg.as_str(),
+                reason => panic!("unexpected error: {:?}", reason),
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "Unknown type `foo`",
+                "Unknown type `bar`",
+                "Unknown type `baz`"
+            ]
+        );
+        let param = |name: &str, ty| Param {
+            is_mut: false,
+            name: Spanned::any(name.to_string()),
+            ty: Spanned::any(ty),
+        };
+        assert_eq!(
+            def,
+            Some(Spanned::any(Stmt::DefFn {
+                attrs: vec![],
+                name: Spanned::any("f".to_string()),
+                args: vec![
+                    param("a", Type::Error),
+                    param("b", Type::I32),
+                    param(
+                        "c",
+                        Type::Fn(FnType {
+                            params: vec![Type::Error],
+                            ret: Box::new(Type::I32),
+                        })
+                    ),
+                ],
+                ret_ty: Spanned::any(Type::Error),
+                body: Spanned::any(Stmt::Block(vec![])),
+            }))
+        );
+    }
+
     #[test]
     fn test_defvar() {
         assert_eq!(
             defvar().parse_test("let var: i32 = 10;"),
             Ok(Spanned::any(Stmt::DefVar {
+                attrs: vec![],
                 is_mut: false,
                 name: Spanned::any("var".to_string()),
                 ty: Spanned::any(Type::I32),
@@ -286,6 +507,7 @@ mod tests {
         assert_eq!(
             defvar().parse_test("let mut var: i32 = 10;"),
             Ok(Spanned::any(Stmt::DefVar {
+                attrs: vec![],
                 is_mut: true,
                 name: Spanned::any("var".to_string()),
                 ty: Spanned::any(Type::I32),
@@ -295,6 +517,7 @@ mod tests {
         assert_eq!(
             defvar().parse_test("let mut var: i32;"),
             Ok(Spanned::any(Stmt::DefVar {
+                attrs: vec![],
                 is_mut: true,
                 name: Spanned::any("var".to_string()),
                 ty: Spanned::any(Type::I32),
@@ -304,6 +527,7 @@ mod tests {
         assert_eq!(
             defvar().parse_test("let   mut   var    :   i32     ;"),
             Ok(Spanned::any(Stmt::DefVar {
+                attrs: vec![],
                 is_mut: true,
                 name: Spanned::any("var".to_string()),
                 ty: Spanned::any(Type::I32),
@@ -311,6 +535,19 @@ mod tests {
             }))
         );
 
+        assert_eq!(
+            defvar().parse_test("#[deny(warnings)]\nlet var: i32;"),
+            Ok(Spanned::any(Stmt::DefVar {
+                attrs: vec![Spanned::any(Attribute::Deny(vec![Spanned::any(
+                    "warnings".to_string()
+                )]))],
+                is_mut: false,
+                name: Spanned::any("var".to_string()),
+                ty: Spanned::any(Type::I32),
+                expr: None,
+            }))
+        );
+
         assert!(defvar().parse_test("let var: type = 10").is_err());
         assert!(defvar().parse_test("let mut var: type = 10").is_err());
 
@@ -328,6 +565,17 @@ mod tests {
             block(None).parse_test("{     }"),
             Ok(Spanned::any(Stmt::Block(vec![])))
         );
+        assert_eq!(
+            block(None).parse_test("{ /* comment */ }"),
+            Ok(Spanned::any(Stmt::Block(vec![])))
+        );
+        assert_eq!(
+            block(None).parse_test("{ {} {} }"),
+            Ok(Spanned::any(Stmt::Block(vec![
+                Spanned::any(Stmt::Block(vec![])),
+                Spanned::any(Stmt::Block(vec![])),
+            ])))
+        );
         assert_eq!(
             block(None).parse_test(
                 r#"{
@@ -340,12 +588,14 @@ mod tests {
             ),
             Ok(Spanned::any(Stmt::Block(vec![
                 Spanned::any(Stmt::DefVar {
+                    attrs: vec![],
                     is_mut: false,
                     name: Spanned::any("var1".to_string()),
                     ty: Spanned::any(Type::I32),
                     expr: Some(Spanned::any(Expr::I32(10))),
                 }),
                 Spanned::any(Stmt::DefVar {
+                    attrs: vec![],
                     is_mut: true,
                     name: Spanned::any("var2".to_string()),
                     ty: Spanned::any(Type::I32),
@@ -359,10 +609,56 @@ mod tests {
             ])))
         );
         assert!(block(None).parse_test("{     ").is_err());
+        assert!(matches!(
+            block(None).parse_test("{ let var: i32 = 10; ").unwrap_err()[..],
+            [ref err] if matches!(err.reason(), SimpleReason::Unclosed { delimiter: '{', .. })
+        ));
         assert!(block(None).parse_test("  }").is_err());
         assert!(block(None).parse_test("let var: type = 10;").is_err());
     }
 
+    #[test]
+    fn test_block_recovery() {
+        let (block, errors) = block(None).parse_recovery_test(
+            r#"{
+                let x: i32 = 1 +;
+                retrun x;
+                if x { x = ; }
+                return x;
+            "#,
+        );
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(
+            errors.last().unwrap().reason(),
+            SimpleReason::Unclosed { delimiter: '{', .. }
+        ));
+        assert_eq!(
+            block,
+            Some(Spanned::any(Stmt::Block(vec![
+                Spanned::any(Stmt::DefVar {
+                    attrs: vec![],
+                    is_mut: false,
+                    name: Spanned::any("x".to_string()),
+                    ty: Spanned::any(Type::I32),
+                    expr: Some(Spanned::any(Expr::Error)),
+                }),
+                Spanned::any(Stmt::Error),
+                Spanned::any(Stmt::Empty),
+                Spanned::any(Stmt::If {
+                    cond: Spanned::any(Expr::Variable("x".to_string())),
+                    then: Spanned::any(Stmt::Block(vec![
+                        Spanned::any(Stmt::Error),
+                        Spanned::any(Stmt::Empty),
+                    ])),
+                    els: None,
+                }),
+                Spanned::any(Stmt::Return(Some(Spanned::any(Expr::Variable(
+                    "x".to_string()
+                ))))),
+            ])))
+        );
+    }
+
     #[test]
     fn test_stmt() {
         assert_eq!(
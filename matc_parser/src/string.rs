@@ -1,11 +1,13 @@
 /// String Literal Node
+use crate::error::Error;
 use crate::prelude::*;
 use matc_ast::Expr;
 use matc_span::Spanned;
 
 // pointer for i8
 pub(crate) fn string() -> impl Parser<Spanned<Expr>> {
-    filter(|c: &char| c.is_ascii() && *c != '"')
+    filter(|c: &char| *c != '"' && *c != '\\')
+        .or(escape())
         .repeated()
         .delimited_by(just('"'), just('"'))
         .collect::<String>()
@@ -14,6 +16,37 @@ pub(crate) fn string() -> impl Parser<Spanned<Expr>> {
         .boxed()
 }
 
+/// `\n`, `\r`, `\t`, `\0`, `\\`, `\'`, `\"` or `\u{...}` with up to 6 hex digits
+pub(crate) fn escape() -> impl Parser<char> {
+    let unicode = filter(|c: &char| c.is_ascii_hexdigit())
+        .repeated()
+        .at_least(1)
+        .at_most(6)
+        .collect::<String>()
+        .delimited_by(just("u{"), just('}'))
+        .try_map(|hex, span| {
+            u32::from_str_radix(&hex, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| {
+                    Error::custom(span, format!("Invalid unicode escape `\\u{{{}}}`", hex))
+                })
+        });
+
+    just('\\')
+        .ignore_then(choice((
+            just('n').to('\n'),
+            just('r').to('\r'),
+            just('t').to('\t'),
+            just('0').to('\0'),
+            just('\\'),
+            just('\''),
+            just('"'),
+            unicode,
+        )))
+        .boxed()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -36,6 +69,18 @@ mod tests {
             string().parse_test("\"\n\""),
             Ok(Spanned::any(Expr::String("\n".to_string())))
         );
+        assert_eq!(
+            string().parse_test("\"日本語 é\""),
+            Ok(Spanned::any(Expr::String("日本語 é".to_string())))
+        );
+        assert_eq!(
+            string().parse_test(r#""\"\\\n\u{3042}\u{1F600}""#),
+            Ok(Spanned::any(Expr::String("\"\\\nあ😀".to_string())))
+        );
+        assert!(string().parse_test(r#""\q""#).is_err());
+        assert!(string().parse_test(r#""\u{}""#).is_err());
+        assert!(string().parse_test(r#""\u{110000}""#).is_err());
+        assert!(string().parse_test(r#""\u{D800}""#).is_err());
         assert!(string().parse_test("    \"a\"").is_err());
         assert!(string().parse_test("\"a").is_err());
         assert!(string().parse_test("a\"").is_err());
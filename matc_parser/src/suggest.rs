@@ -0,0 +1,65 @@
+/// Suggestions for misspelled names in diagnostics
+use std::cmp::min;
+
+/// Returns the edit distance between `a` and `b`, where a transposition of two adjacent
+/// characters counts as a single edit.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<_> = a.chars().collect();
+    let b: Vec<_> = b.chars().collect();
+    // d[i][j] is the distance between a[..i] and b[..j].
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = min(
+                min(d[i - 1][j] + 1, d[i][j - 1] + 1),
+                d[i - 1][j - 1] + cost,
+            );
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = min(d[i][j], d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Returns the candidate closest to `name`, if any is close enough to be a typo of it.
+pub fn closest<'a, I: IntoIterator<Item = &'a str>>(name: &str, candidates: I) -> Option<&'a str> {
+    let max = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("retrun", "return"), 1);
+        assert_eq!(edit_distance("é", "e"), 1);
+    }
+
+    #[test]
+    fn test_closest() {
+        assert_eq!(closest("i23", ["void", "char", "i32"]), Some("i32"));
+        assert_eq!(closest("quux", ["qux", "quuux", "bar"]), Some("qux"));
+        assert_eq!(closest("x", ["y"]), Some("y"));
+        assert_eq!(closest("foo", ["bar", "foo"]), None);
+        assert_eq!(closest("fuga", ["void", "char", "i32"]), None);
+    }
+}
@@ -1,14 +1,51 @@
 /// Type Node
+use crate::error::Error;
+use crate::ident::{keyword, raw_ident};
 use crate::prelude::*;
-use matc_ast::Type;
+use crate::suggest::closest;
+use matc_ast::{FnType, Type};
 use matc_span::Spanned;
 
+const TYPES: &[&str] = &["void", "char", "i32"];
+
 pub(crate) fn typeref() -> impl Parser<Spanned<Type>> {
-    choice((
-        text::keyword("void").to(Type::Void),
-        text::keyword("char").to(Type::I8),
-        text::keyword("i32").to(Type::I32),
-    ))
+    recursive(|ty| {
+        // fn(type1, ...) -> type
+        let fn_type = keyword("fn")
+            .ignore_then(
+                ty.clone()
+                    .padded()
+                    .separated_by(just(','))
+                    .delimited_by(just('(').padded(), just(')')),
+            )
+            .then_ignore(just("->").padded())
+            .then(ty)
+            .map(|(params, ret): (Vec<Type>, Type)| {
+                Type::Fn(FnType {
+                    params,
+                    ret: Box::new(ret),
+                })
+            });
+
+        choice((
+            keyword("void").to(Type::Void),
+            keyword("char").to(Type::I8),
+            keyword("i32").to(Type::I32),
+            fn_type,
+            // Report other names as unknown types rather than unexpected tokens, and go on to
+            // report the other types of a signature too.
+            raw_ident().validate(|name: String, span, emit| {
+                let err = Error::unknown_type(span, &name);
+                emit(match closest(&name, TYPES.iter().copied()) {
+                    Some(ty) => {
+                        err.with_help(format!("a type with a similar name exists: `{}`", ty))
+                    }
+                    None => err,
+                });
+                Type::Error
+            }),
+        ))
+    })
     .map_with_span(Spanned::new)
     .boxed()
 }
@@ -16,11 +53,34 @@ pub(crate) fn typeref() -> impl Parser<Spanned<Type>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Unknown;
 
     #[test]
     fn test_typeref() {
         assert_eq!(typeref().parse_test("void"), Ok(Spanned::any(Type::Void)));
         assert_eq!(typeref().parse_test("char"), Ok(Spanned::any(Type::I8)));
         assert_eq!(typeref().parse_test("i32"), Ok(Spanned::any(Type::I32)));
+
+        assert_eq!(
+            typeref().parse_test("fn( i32 , char )->fn() -> void"),
+            Ok(Spanned::any(Type::Fn(FnType {
+                params: vec![Type::I32, Type::I8],
+                ret: Box::new(Type::Fn(FnType {
+                    params: vec![],
+                    ret: Box::new(Type::Void),
+                })),
+            })))
+        );
+        assert!(typeref().parse_test("fn(i32)").is_err());
+
+        let errors = typeref().parse_test("i23").unwrap_err();
+        assert_eq!(errors[0].unknown(), Some(Unknown::Type));
+        assert_eq!(
+            errors[0].help(),
+            Some("a type with a similar name exists: `i32`")
+        );
+        let errors = typeref().parse_test("fuga").unwrap_err();
+        assert_eq!(errors[0].unknown(), Some(Unknown::Type));
+        assert_eq!(errors[0].help(), None);
     }
 }
@@ -1,7 +1,13 @@
+mod line_index;
+mod node_id;
+mod source_db;
 mod span;
 mod spanned;
 mod src;
 
+pub use line_index::{Encoding, LineCol, LineIndex};
+pub use node_id::NodeId;
+pub use source_db::SourceDb;
 pub use span::Span;
 pub use spanned::Spanned;
-pub use src::SrcId;
+pub use src::{SrcId, STDIN};
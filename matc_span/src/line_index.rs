@@ -0,0 +1,139 @@
+/// A 1-based line and column in a text
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Unit in which columns are counted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16,
+    Char,
+}
+
+impl Encoding {
+    fn len(self, c: char) -> usize {
+        match self {
+            Encoding::Utf8 => c.len_utf8(),
+            Encoding::Utf16 => c.len_utf16(),
+            Encoding::Char => 1,
+        }
+    }
+}
+
+/// Converts byte offsets in a text from and to line/column pairs
+#[derive(Clone, Debug)]
+pub struct LineIndex {
+    text: String,
+    /// Byte offsets where each line starts
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the line and column of `offset`, which is clamped to the text and rounded down to
+    /// a char boundary.
+    pub fn line_col(&self, offset: usize, encoding: Encoding) -> LineCol {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let column = self.text[self.line_starts[line]..offset]
+            .chars()
+            .map(|c| encoding.len(c))
+            .sum::<usize>();
+        LineCol {
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+
+    /// Returns the byte offset of `line_col`; the inverse of `line_col`.
+    ///
+    /// A column past the end of its line is clamped to the line end, and one inside a char (e.g.
+    /// in a UTF-16 surrogate pair) moves to the next char.
+    pub fn offset(&self, line_col: LineCol, encoding: Encoding) -> Option<usize> {
+        let start = *self.line_starts.get(line_col.line.checked_sub(1)?)?;
+        let line = &self.text[start..];
+        let line = &line[..line.find('\n').unwrap_or(line.len())];
+
+        let mut column = 1;
+        for (i, c) in line.char_indices() {
+            if column >= line_col.column {
+                return Some(start + i);
+            }
+            column += encoding.len(c);
+        }
+        Some(start + line.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(line: usize, column: usize) -> LineCol {
+        LineCol { line, column }
+    }
+
+    #[test]
+    fn test_line_col() {
+        // `😀` takes 4 bytes and a surrogate pair in UTF-16, and `é` takes 2 bytes.
+        let index = LineIndex::new("a😀b\n// é!\nlet s = \"é😀\";");
+        for (offset, utf8, utf16, chars) in [
+            (0, at(1, 1), at(1, 1), at(1, 1)),
+            (1, at(1, 2), at(1, 2), at(1, 2)),
+            (5, at(1, 6), at(1, 4), at(1, 3)),
+            (6, at(1, 7), at(1, 5), at(1, 4)),
+            (7, at(2, 1), at(2, 1), at(2, 1)),
+            (12, at(2, 6), at(2, 5), at(2, 5)),
+            (14, at(3, 1), at(3, 1), at(3, 1)),
+            (23, at(3, 10), at(3, 10), at(3, 10)),
+            (25, at(3, 12), at(3, 11), at(3, 11)),
+            (29, at(3, 16), at(3, 13), at(3, 12)),
+            (31, at(3, 18), at(3, 15), at(3, 14)),
+        ] {
+            assert_eq!(index.line_col(offset, Encoding::Utf8), utf8);
+            assert_eq!(index.line_col(offset, Encoding::Utf16), utf16);
+            assert_eq!(index.line_col(offset, Encoding::Char), chars);
+            assert_eq!(index.offset(utf8, Encoding::Utf8), Some(offset));
+            assert_eq!(index.offset(utf16, Encoding::Utf16), Some(offset));
+            assert_eq!(index.offset(chars, Encoding::Char), Some(offset));
+        }
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let index = LineIndex::new("a😀\nb");
+        // Inside `😀`
+        assert_eq!(index.line_col(3, Encoding::Char), at(1, 2));
+        assert_eq!(index.offset(at(1, 3), Encoding::Utf16), Some(5));
+        // Past the end
+        assert_eq!(index.line_col(100, Encoding::Char), at(2, 2));
+        assert_eq!(index.offset(at(1, 100), Encoding::Char), Some(5));
+        assert_eq!(index.offset(at(2, 100), Encoding::Char), Some(7));
+        assert_eq!(index.offset(at(3, 1), Encoding::Char), None);
+        assert_eq!(index.offset(at(0, 1), Encoding::Char), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let index = LineIndex::new("");
+        assert_eq!(index.line_col(0, Encoding::Utf8), at(1, 1));
+        assert_eq!(index.offset(at(1, 1), Encoding::Utf8), Some(0));
+    }
+}
@@ -0,0 +1,37 @@
+use std::fmt;
+
+/// Identifies a statement, an expression or a defined name in the syntax tree.
+///
+/// The parser leaves every node `NodeId::DUMMY` and they are numbered before the analysis, so
+/// that its results can be kept in side tables keyed by nodes.
+#[derive(Clone, PartialEq, Copy, Hash, Eq, PartialOrd, Ord)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    pub const DUMMY: Self = Self(u32::MAX);
+
+    pub fn new(id: u32) -> Self {
+        assert_ne!(id, u32::MAX, "attempted to create a dummy ID");
+        Self(id)
+    }
+
+    pub fn is_dummy(self) -> bool {
+        self == Self::DUMMY
+    }
+}
+
+impl Default for NodeId {
+    fn default() -> Self {
+        Self::DUMMY
+    }
+}
+
+impl fmt::Debug for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_dummy() {
+            write!(f, "#?")
+        } else {
+            write!(f, "#{}", self.0)
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{LineIndex, SrcId};
+use ariadne::{Cache, Source};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Texts and line indices of the sources which spans point to, to render diagnostics across files
+#[derive(Default)]
+pub struct SourceDb {
+    sources: HashMap<SrcId, (Source<String>, LineIndex)>,
+}
+
+impl SourceDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_source(mut self, src: SrcId, text: String) -> Self {
+        self.insert(src, text);
+        self
+    }
+
+    /// Adds the text of `src`, replacing the previous one if any.
+    pub fn insert(&mut self, src: SrcId, text: String) {
+        let index = LineIndex::new(text.as_str());
+        self.sources.insert(src, (Source::from(text), index));
+    }
+
+    pub fn text(&self, src: SrcId) -> Option<&str> {
+        self.line_index(src).map(LineIndex::text)
+    }
+
+    pub fn line_index(&self, src: SrcId) -> Option<&LineIndex> {
+        self.sources.get(&src).map(|(_, index)| index)
+    }
+}
+
+impl Cache<SrcId> for &SourceDb {
+    type Storage = String;
+
+    fn fetch(&mut self, src: &SrcId) -> Result<&Source<String>, Box<dyn fmt::Debug + '_>> {
+        self.sources
+            .get(src)
+            .map(|(source, _)| source)
+            .ok_or_else(|| Box::new(format!("Unknown source `{}`", src)) as _)
+    }
+
+    fn display<'a>(&self, src: &'a SrcId) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(src))
+    }
+}
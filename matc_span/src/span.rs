@@ -5,14 +5,29 @@ use std::fmt;
 use std::hash::Hash;
 use std::ops::Range;
 
+/// A range of byte offsets in a source.
+///
 /// Range does not implements `Copy` (#27186), so here we use (usize, usize) instead
 #[derive(Clone, PartialEq, Copy, Hash, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SerdeSpan", try_from = "SerdeSpan")
+)]
 pub struct Span {
     src: SrcId,
     range: (usize, usize),
 }
 
 impl Span {
+    pub fn new(src: SrcId, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end);
+        Self {
+            src,
+            range: (range.start, range.end),
+        }
+    }
+
     pub fn range(&self) -> Range<usize> {
         self.start()..self.end()
     }
@@ -42,6 +57,41 @@ impl Span {
     }
 }
 
+/// `Span` as a file and a byte range, e.g. `{"file": "a.mat", "start": 0, "end": 1}`
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerdeSpan {
+    file: SrcId,
+    start: usize,
+    end: usize,
+}
+
+#[cfg(feature = "serde")]
+impl From<Span> for SerdeSpan {
+    fn from(span: Span) -> Self {
+        Self {
+            file: span.src,
+            start: span.start(),
+            end: span.end(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerdeSpan> for Span {
+    type Error = String;
+
+    fn try_from(span: SerdeSpan) -> Result<Self, Self::Error> {
+        if span.start > span.end {
+            return Err(format!(
+                "span ends at {} before its start {}",
+                span.end, span.start
+            ));
+        }
+        Ok(Span::new(span.file, span.start..span.end))
+    }
+}
+
 impl fmt::Debug for Span {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}:{:?}", self.src, self.range())
@@ -53,11 +103,7 @@ impl chumsky::Span for Span {
     type Offset = usize;
 
     fn new(src: Self::Context, range: Range<Self::Offset>) -> Self {
-        assert!(range.start <= range.end);
-        Self {
-            src,
-            range: (range.start, range.end),
-        }
+        Span::new(src, range)
     }
 
     fn context(&self) -> Self::Context {
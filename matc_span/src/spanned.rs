@@ -1,12 +1,16 @@
-use crate::Span;
+use crate::{NodeId, Span};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::ops::{Deref, DerefMut};
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Spanned<T> {
     pub value: Box<T>,
     pub span: Span,
+    /// Not serialized since IDs are assigned to each tree before the analysis
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub id: NodeId,
 }
 
 impl<T> Spanned<T> {
@@ -14,6 +18,7 @@ impl<T> Spanned<T> {
         Spanned {
             value: Box::new(value),
             span,
+            id: NodeId::DUMMY,
         }
     }
 
@@ -47,7 +52,7 @@ impl<T> DerefMut for Spanned<T> {
 
 impl<T: PartialEq> PartialEq for Spanned<T> {
     fn eq(&self, other: &Self) -> bool {
-        // Ignore checking Span; nothing makes sense, particularly on tests.
+        // Ignore checking Span and NodeId; nothing makes sense, particularly on tests.
         self.value == other.value
     }
 }
@@ -56,7 +61,7 @@ impl<T: Eq> Eq for Spanned<T> {}
 
 impl<T: Hash> Hash for Spanned<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        // Ignore Span.
+        // Ignore Span and NodeId.
         self.value.hash(state);
     }
 }
@@ -64,9 +69,13 @@ impl<T: Hash> Hash for Spanned<T> {
 impl<T: fmt::Debug> fmt::Debug for Spanned<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if f.alternate() {
-            write!(f, "{:#?} @ {:?}", self.value, self.span)
+            write!(f, "{:#?} @ {:?}", self.value, self.span)?;
         } else {
-            write!(f, "{:?} @ {:?}", self.value, self.span)
+            write!(f, "{:?} @ {:?}", self.value, self.span)?;
         }
+        if !self.id.is_dummy() {
+            write!(f, " {:?}", self.id)?;
+        }
+        Ok(())
     }
 }
@@ -1,6 +1,9 @@
 use internment::Intern;
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Path of the standard input as a source
+pub const STDIN: &str = "<stdin>";
 
 #[derive(Clone, PartialEq, Copy, Hash, Eq)]
 pub struct SrcId(Intern<Vec<String>>);
@@ -15,17 +18,27 @@ impl SrcId {
         ))
     }
 
+    /// The standard input read as a source
+    pub fn stdin() -> Self {
+        Self::from_path(STDIN)
+    }
+
     pub fn any() -> Self {
         Self(Intern::new(Vec::new()))
     }
+
+    /// The path the source was read from
+    pub fn path(&self) -> PathBuf {
+        self.0.iter().collect()
+    }
 }
 
 impl fmt::Debug for SrcId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.0.len() == 0 {
+        if self.0.is_empty() {
             write!(f, "unknown")
         } else {
-            write!(f, "{}", self.0.clone().join("/"))
+            write!(f, "{}", self.path().display())
         }
     }
 }
@@ -35,3 +48,39 @@ impl fmt::Display for SrcId {
         write!(f, "{:?}", self)
     }
 }
+
+/// Serialized as its path
+#[cfg(feature = "serde")]
+impl serde::Serialize for SrcId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SrcId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(SrcId::from_path("main.mat").to_string(), "main.mat");
+        assert_eq!(
+            SrcId::from_path("src/./main.mat").to_string(),
+            "src/main.mat"
+        );
+        assert_eq!(SrcId::from_path("/tmp/t/w.mat").to_string(), "/tmp/t/w.mat");
+        assert_eq!(
+            SrcId::from_path("/tmp/t/w.mat").path(),
+            Path::new("/tmp/t/w.mat")
+        );
+        assert_eq!(SrcId::stdin().to_string(), STDIN);
+        assert_eq!(SrcId::any().to_string(), "unknown");
+    }
+}
@@ -0,0 +1,40 @@
+use matc_ast::Type;
+
+/// Functions provided by the runtime, which can be called without any definition.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Builtin {
+    pub(crate) name: &'static str,
+    pub(crate) arity: usize,
+    pub(crate) ret_ty: Type,
+}
+
+pub(crate) static BUILTINS: [Builtin; 4] = [
+    // fn putchar(c: char) -> i32
+    Builtin {
+        name: "putchar",
+        ret_ty: Type::I32,
+        arity: 1,
+    },
+    // fn getchar() -> i32
+    Builtin {
+        name: "getchar",
+        ret_ty: Type::I32,
+        arity: 0,
+    },
+    // fn puts(s: string) -> i32
+    Builtin {
+        name: "puts",
+        ret_ty: Type::I32,
+        arity: 1,
+    },
+    // fn print_int(n: i32) -> void
+    Builtin {
+        name: "print_int",
+        ret_ty: Type::Void,
+        arity: 1,
+    },
+];
+
+pub(crate) fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
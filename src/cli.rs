@@ -0,0 +1,404 @@
+use crate::diag::lint::{self, Level, LintLevels};
+use crate::diag::{self, Emit, Emitter, ErrorFormat};
+use crate::hir::Hir;
+use crate::util::pluralize;
+use crate::{formatter, interp, json, lsp, repl, sema};
+use anyhow::{anyhow, bail};
+use clap::{ArgGroup, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use debug_print::debug_eprintln;
+use matc_ast::Ast;
+use matc_span::{SourceDb, SrcId, STDIN};
+use std::fs::{read_to_string, write};
+use std::io::{stdin, stdout, Read};
+use std::path::Path;
+use std::process::exit;
+
+#[derive(Parser)]
+#[clap(version, about, long_about = None)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[clap(group(
+    ArgGroup::new("dumps")
+        .args(&[
+            "dump_tokens",
+            "dump_ast",
+            "dump_hir",
+            "dump_scopes",
+            "dump_mir",
+            "dump_asm",
+            "print_asm"
+        ]),
+))]
+struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// Source file to compile, or `-` for the standard input
+    #[clap(required_unless_present = "explain")]
+    source: Option<String>,
+
+    /// Explain a diagnostic code, e.g. `E0101`
+    #[clap(long, value_name = "CODE", conflicts_with = "source")]
+    explain: Option<String>,
+
+    /// Dump tokens
+    #[clap(long)]
+    dump_tokens: bool,
+
+    /// Dump AST
+    #[clap(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "debug"
+    )]
+    dump_ast: Option<DumpFormat>,
+
+    /// Dump HIR
+    #[clap(long)]
+    dump_hir: bool,
+
+    /// Dump the scopes of functions and blocks with their definitions
+    #[clap(long)]
+    dump_scopes: bool,
+
+    /// Dump MIR
+    #[clap(long)]
+    dump_mir: bool,
+
+    /// Dump structured assembly
+    #[clap(long)]
+    dump_asm: bool,
+
+    /// Print raw assembly
+    #[clap(long)]
+    print_asm: bool,
+
+    /// Format of diagnostics
+    #[clap(long, value_enum, global = true, default_value_t)]
+    error_format: ErrorFormat,
+
+    /// Allow a lint, or every lint with `warnings`
+    #[clap(short = 'A', long = "allow", value_name = "LINT", global = true)]
+    allow: Vec<String>,
+
+    /// Warn on a lint
+    #[clap(short = 'W', long = "warn", value_name = "LINT", global = true)]
+    warn: Vec<String>,
+
+    /// Deny a lint, failing the compilation on it
+    #[clap(short = 'D', long = "deny", value_name = "LINT", global = true)]
+    deny: Vec<String>,
+}
+
+#[derive(clap::ValueEnum, Debug, PartialEq, Clone, Copy)]
+enum DumpFormat {
+    /// The Rust debug representation
+    Debug,
+    /// The formatted source code
+    Pretty,
+    /// JSON which can be loaded again as a `.json` source
+    Json,
+}
+
+/// Collects `-A`, `-W` and `-D` in the order of the command line, where later ones win.
+fn lint_levels(matches: &ArgMatches) -> anyhow::Result<LintLevels> {
+    let mut flags = Vec::new();
+    for (id, level) in [
+        ("allow", Level::Allow),
+        ("warn", Level::Warn),
+        ("deny", Level::Deny),
+    ] {
+        if let (Some(lints), Some(indices)) =
+            (matches.get_many::<String>(id), matches.indices_of(id))
+        {
+            flags.extend(indices.zip(lints).map(|(index, lint)| (index, lint, level)));
+        }
+    }
+    flags.sort_by_key(|(index, ..)| *index);
+
+    let mut levels = LintLevels::new();
+    for (_, lint, level) in flags {
+        if !lint::is_known(lint) {
+            bail!("Unknown lint `{}`", lint);
+        }
+        levels.push(None, lint.clone(), level);
+    }
+    Ok(levels)
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a program with the interpreter
+    Run {
+        /// Source file to run, or `-` for the standard input
+        source: String,
+    },
+    /// Start an interactive session
+    Repl,
+    /// Start a language server on stdio
+    Lsp,
+    /// Format source files in place
+    Fmt {
+        /// Source files to format, or `-` for the standard input
+        #[clap(required = true)]
+        sources: Vec<String>,
+
+        /// Check if the files are formatted without modifying them
+        #[clap(long)]
+        check: bool,
+    },
+}
+
+fn parse(
+    args: &Args,
+    (ast, errors): (Option<Ast>, Vec<matc_parser::error::Error>),
+    code: &str,
+    emitter: &mut Emitter,
+) -> Result<(), Box<dyn Emit>> {
+    let Some(ast) = ast else {
+        return Err(Box::new(errors));
+    };
+    if let Some(format) = args.dump_ast {
+        match format {
+            DumpFormat::Debug => println!("{:#?}", ast),
+            // The formatter cannot print the placeholders of syntax errors.
+            DumpFormat::Pretty if errors.is_empty() => print!("{}", formatter::format(&ast, code)),
+            DumpFormat::Pretty => {}
+            DumpFormat::Json => println!("{}", json::to_json(&ast)),
+        }
+        return match errors.is_empty() {
+            true => Ok(()),
+            false => Err(Box::new(errors)),
+        };
+    }
+    if errors.is_empty() {
+        debug_eprintln!("Info: Parse has been completed successfully.");
+    }
+
+    let hir = analyze(ast, errors, emitter)?;
+    debug_eprintln!("Info: Semantic analysis has been completed successfully.");
+    if args.dump_hir {
+        println!("{:#?}", hir);
+        return Ok(());
+    }
+    if args.dump_scopes {
+        print!("{}", hir.dump_scopes());
+        return Ok(());
+    }
+    Ok(())
+}
+
+/// Analyzes `ast` even if it is recovered from syntax `errors` to report semantic errors along
+/// with them. Only warnings are emitted here, while errors are returned with them so that every
+/// diagnostic is emitted at once in order.
+fn analyze(
+    ast: Ast,
+    errors: Vec<matc_parser::error::Error>,
+    emitter: &mut Emitter,
+) -> Result<Hir, Box<dyn Emit>> {
+    let (hir, sema_diagnostics) = sema::analyze(ast, emitter.levels_mut());
+    let diagnostics = [errors.diagnostics(), sema_diagnostics].concat();
+    if diagnostics.count() > 0 {
+        return Err(Box::new(diagnostics));
+    }
+    emitter.emit(&diagnostics);
+    Ok(hir)
+}
+
+/// Reads a source file, or the standard input for `-`.
+fn read_source(source: &str) -> anyhow::Result<(&Path, String)> {
+    if source == "-" {
+        let mut code = String::new();
+        stdin().read_to_string(&mut code)?;
+        return Ok((Path::new(STDIN), code));
+    }
+
+    let path = Path::new(source);
+    if path.extension().filter(|&ext| ext == "mat").is_none() && !is_json(path) {
+        bail!("Source file extension should be `.mat` or `.json`");
+    }
+    Ok((path, read_to_string(path)?))
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().filter(|&ext| ext == "json").is_some()
+}
+
+/// Parses `code`, or loads an AST dumped by `--dump-ast=json` from a `.json` source and adds the
+/// files its spans point to into `db` to render diagnostics on them.
+fn parse_source(
+    source: &Path,
+    code: &str,
+    db: &mut SourceDb,
+) -> anyhow::Result<(Option<Ast>, Vec<matc_parser::error::Error>)> {
+    if !is_json(source) {
+        return Ok(matc_parser::parse_recovery(source, code));
+    }
+
+    let ast = serde_json::from_str(code)
+        .map_err(|err| err.to_string())
+        .and_then(|json| {
+            for file in json::files(&json) {
+                if let Ok(text) = read_to_string(&file) {
+                    db.insert(SrcId::from_path(&file), text);
+                }
+            }
+            json::from_json(json)
+        })
+        .map_err(|err| anyhow!("Could not load an AST from `{:?}`: {}", source, err))?;
+    Ok((Some(ast), Vec::new()))
+}
+
+fn compile(args: &Args, levels: LintLevels, source: &str) -> anyhow::Result<()> {
+    let (source, code) = read_source(source)?;
+    let mut db = SourceDb::new().with_source(SrcId::from_path(source), code.clone());
+    let parsed = parse_source(source, &code, &mut db)?;
+    if args.dump_ast == Some(DumpFormat::Pretty) && is_json(source) {
+        bail!("`--dump-ast=pretty` needs the source code, not an AST");
+    }
+
+    let mut emitter = Emitter::new(args.error_format, &db).with_levels(levels);
+    let result = parse(args, parsed, &code, &mut emitter);
+    if let Err(errors) = &result {
+        emitter.emit(errors.as_ref());
+    }
+    emitter.finish();
+    if let Err(errors) = result {
+        bail!(
+            "Could not compile `{:?}` due to {} previous {}",
+            source,
+            errors.count(),
+            pluralize("error", errors.count()),
+        );
+    }
+    Ok(())
+}
+
+fn run(source: &str, error_format: ErrorFormat, levels: LintLevels) -> anyhow::Result<()> {
+    let (source, code) = read_source(source)?;
+    let mut db = SourceDb::new().with_source(SrcId::from_path(source), code.clone());
+    let parsed = parse_source(source, &code, &mut db)?;
+
+    let mut emitter = Emitter::new(error_format, &db).with_levels(levels);
+    let hir = match parsed {
+        (Some(ast), errors) => analyze(ast, errors, &mut emitter),
+        (None, errors) => Err(Box::new(errors) as Box<dyn Emit>),
+    };
+    let hir = match hir {
+        Ok(hir) => hir,
+        Err(errors) => {
+            emitter.emit(errors.as_ref());
+            emitter.finish();
+            bail!(
+                "Could not run `{:?}` due to {} previous {}",
+                source,
+                errors.count(),
+                pluralize("error", errors.count()),
+            );
+        }
+    };
+    if !hir
+        .defined_functions()
+        .iter()
+        .any(|fun| *fun.name.value == "main")
+    {
+        emitter.finish();
+        bail!("`main` function not found in `{:?}`", source);
+    }
+
+    // Warnings have been emitted before the program starts, except for SARIF, which has all
+    // diagnostics in `finish`. A runtime fault is reported after the program output.
+    match interp::run(&hir, &mut stdin().lock(), &mut stdout().lock()) {
+        Ok(status) => {
+            emitter.finish();
+            exit(status)
+        }
+        Err(fault) => {
+            emitter.emit(&fault);
+            emitter.finish();
+            bail!("Could not run `{:?}` due to a runtime error", source);
+        }
+    }
+}
+
+fn fmt(sources: &[String], check: bool) -> anyhow::Result<()> {
+    let mut unformatted = 0;
+    for source in sources {
+        let (source, code) = read_source(source)?;
+        if is_json(source) {
+            bail!(
+                "Could not format `{:?}` since it is not source code",
+                source
+            );
+        }
+
+        let ast = match matc_parser::parse(source, &code) {
+            Ok(ast) => ast,
+            Err(errors) => {
+                errors.emit(&SourceDb::new().with_source(SrcId::from_path(source), code));
+                bail!(
+                    "Could not format `{:?}` due to {} previous {}",
+                    source,
+                    errors.count(),
+                    pluralize("error", errors.count()),
+                );
+            }
+        };
+        let formatted = formatter::format(&ast, &code);
+        if source == Path::new(STDIN) && !check {
+            print!("{}", formatted);
+            continue;
+        }
+        if formatted == code {
+            continue;
+        }
+
+        if check {
+            println!("`{:?}` is not formatted", source);
+            unformatted += 1;
+        } else {
+            write(source, formatted)?;
+        }
+    }
+
+    if unformatted > 0 {
+        bail!(
+            "{} {} not formatted",
+            unformatted,
+            pluralize("file", unformatted)
+        );
+    }
+    Ok(())
+}
+
+fn explain(code: &str) -> anyhow::Result<()> {
+    match diag::explain(code) {
+        Some(explanation) => {
+            println!("{}", explanation);
+            Ok(())
+        }
+        None => bail!("`{}` is not a valid diagnostic code", code),
+    }
+}
+
+/// The entry point of the `matc` command
+pub fn main() -> anyhow::Result<()> {
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let levels = match matches.subcommand() {
+        Some((_, matches)) => lint_levels(matches)?,
+        None => lint_levels(&matches)?,
+    };
+    match &args.command {
+        Some(Command::Run { source }) => run(source, args.error_format, levels),
+        Some(Command::Repl) => repl::run(&mut stdin().lock(), &mut stdout().lock()),
+        Some(Command::Lsp) => lsp::run(&mut stdin().lock(), &mut stdout().lock()),
+        Some(Command::Fmt { sources, check }) => fmt(sources, *check),
+        None => match &args.explain {
+            Some(code) => explain(code),
+            None => compile(&args, levels, args.source.as_ref().unwrap()),
+        },
+    }
+}
@@ -1,7 +1,12 @@
+mod diagnostic;
 mod emit;
+mod explain;
+pub(crate) mod lint;
 mod parser;
 
-pub(crate) use emit::{emit, Emit};
+pub use diagnostic::{Diagnostic, Label, Severity};
+pub(crate) use emit::{Emit, Emitter, ErrorFormat};
+pub(crate) use explain::explain;
 
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct Diagnostics<W, E> {
@@ -14,14 +19,10 @@ where
     Vec<W>: Emit,
     Vec<E>: Emit,
 {
-    fn emit(&self, code: &str) {
-        self.warnings.emit(code);
-        self.errors.emit(code);
-    }
-
-    fn count(&self) -> usize {
-        // Ignore warnings
-        self.errors.count()
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.warnings.diagnostics();
+        diagnostics.extend(self.errors.diagnostics());
+        diagnostics
     }
 }
 
@@ -33,10 +34,6 @@ impl<W, E> Diagnostics<W, E> {
         }
     }
 
-    pub(crate) fn has_err(&self) -> bool {
-        !self.errors.is_empty()
-    }
-
     pub(crate) fn push_warn(&mut self, warn: W) {
         self.warnings.push(warn);
     }
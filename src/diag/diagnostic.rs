@@ -0,0 +1,109 @@
+use crate::diag::Emit;
+use ariadne::Color;
+use matc_span::Span;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: Option<String>,
+    pub(crate) color: Option<Color>,
+}
+
+impl Label {
+    pub(crate) fn new(span: Span) -> Self {
+        Self {
+            span,
+            message: None,
+            color: None,
+        }
+    }
+
+    pub(crate) fn with_message<M: ToString>(mut self, message: M) -> Self {
+        self.message = Some(message.to_string());
+        self
+    }
+
+    pub(crate) fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+}
+
+/// The format-independent model of a diagnostic, from which every renderer is built
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<&'static str>,
+    /// The lint name of a warning, by which its level can be changed
+    pub lint: Option<&'static str>,
+    pub message: String,
+    /// The primary span
+    pub span: Span,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub helps: Vec<String>,
+}
+
+impl Diagnostic {
+    pub(crate) fn new<M: ToString>(severity: Severity, span: Span, message: M) -> Self {
+        Self {
+            severity,
+            code: None,
+            lint: None,
+            message: message.to_string(),
+            span,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            helps: Vec::new(),
+        }
+    }
+
+    pub(crate) fn error<M: ToString>(span: Span, message: M) -> Self {
+        Self::new(Severity::Error, span, message)
+    }
+
+    pub(crate) fn warning<M: ToString>(span: Span, message: M) -> Self {
+        Self::new(Severity::Warning, span, message)
+    }
+
+    pub(crate) fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub(crate) fn with_lint(mut self, lint: &'static str) -> Self {
+        self.lint = Some(lint);
+        self
+    }
+
+    pub(crate) fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    pub(crate) fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub(crate) fn with_note<N: ToString>(mut self, note: N) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub(crate) fn with_help<H: ToString>(mut self, help: H) -> Self {
+        self.helps.push(help.to_string());
+        self
+    }
+}
+
+impl Emit for Diagnostic {
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        vec![self.clone()]
+    }
+}
@@ -1,42 +1,477 @@
-use ariadne::{Label, Report, ReportKind, Source};
-use matc_span::Span;
+use crate::diag::diagnostic::{Diagnostic, Severity};
+use crate::diag::lint::LintLevels;
+use ariadne::{Config, Fmt, IndexType, Report, ReportKind};
+use matc_span::{Encoding, LineCol, SourceDb, Span, SrcId};
+use serde_json::{json, Value};
+use std::io::{self, Write};
 
 pub(crate) trait Emit {
-    fn emit(&self, code: &str);
+    fn diagnostics(&self) -> Vec<Diagnostic>;
+
+    /// Renders the diagnostics as human-readable text to the standard error.
+    fn emit(&self, db: &SourceDb) {
+        for diagnostic in self.diagnostics() {
+            render(&diagnostic, db);
+        }
+    }
+
+    /// Counts errors including warnings promoted to errors.
     fn count(&self) -> usize {
-        1
+        self.diagnostics()
+            .iter()
+            .filter(|diagnostic| diagnostic.is_error())
+            .count()
     }
 }
 
 impl<T: Emit> Emit for Vec<T> {
-    fn emit(&self, code: &str) {
-        for emitter in self {
-            emitter.emit(code);
-        }
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.iter().flat_map(Emit::diagnostics).collect()
     }
 
     fn count(&self) -> usize {
-        self.len()
+        self.iter().map(Emit::count).sum()
     }
 }
 
-pub(crate) fn emit(
-    code: &str,
-    span: Span,
-    message: String,
-    labels: Vec<Label<Span>>,
-    notes: Vec<String>,
-) {
-    let mut report =
-        Report::build(ReportKind::Error, span).with_message(message);
-    for label in labels {
-        report = report.with_label(label);
+#[derive(clap::ValueEnum, Debug, Default, PartialEq, Clone, Copy)]
+pub(crate) enum ErrorFormat {
+    /// Colored text with source snippets
+    #[default]
+    Human,
+    /// A JSON object per line for each diagnostic
+    Json,
+    /// A single SARIF 2.1.0 document
+    Sarif,
+}
+
+/// Renders diagnostics in a format to the standard error, keeping the standard output for
+/// programs run and dumps; call `finish` at last since some formats need all of them.
+pub(crate) struct Emitter<'a> {
+    format: ErrorFormat,
+    db: &'a SourceDb,
+    levels: LintLevels,
+    results: Vec<Value>,
+    out: Box<dyn Write + 'a>,
+}
+
+impl<'a> Emitter<'a> {
+    pub(crate) fn new(format: ErrorFormat, db: &'a SourceDb) -> Self {
+        Self {
+            format,
+            db,
+            levels: LintLevels::new(),
+            results: Vec::new(),
+            out: Box::new(io::stderr()),
+        }
     }
-    for note in notes {
+
+    /// Renders to `out` instead of the standard error.
+    pub(crate) fn with_output(mut self, out: &'a mut dyn Write) -> Self {
+        self.out = Box::new(out);
+        self
+    }
+
+    pub(crate) fn with_levels(mut self, levels: LintLevels) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    pub(crate) fn levels_mut(&mut self) -> &mut LintLevels {
+        &mut self.levels
+    }
+
+    /// Emits errors first, then warnings, each in the order of their positions.
+    pub(crate) fn emit(&mut self, emit: &dyn Emit) {
+        let mut diagnostics = emit.diagnostics();
+        diagnostics.sort_by_key(|diagnostic| {
+            let span = diagnostic.span;
+            (
+                !diagnostic.is_error(),
+                span.src().path(),
+                span.range().start,
+            )
+        });
+        for diagnostic in diagnostics {
+            match self.format {
+                ErrorFormat::Human => report(&diagnostic).write(self.db, &mut self.out).unwrap(),
+                ErrorFormat::Json => {
+                    writeln!(self.out, "{}", to_json(&diagnostic, self.db)).unwrap()
+                }
+                ErrorFormat::Sarif => self.results.push(to_sarif_result(&diagnostic, self.db)),
+            }
+        }
+    }
+
+    pub(crate) fn finish(mut self) {
+        if self.format == ErrorFormat::Sarif {
+            writeln!(self.out, "{}", to_sarif(self.results)).unwrap();
+        }
+    }
+}
+
+fn render(diagnostic: &Diagnostic, db: &SourceDb) {
+    report(diagnostic).eprint(db).unwrap();
+}
+
+fn report(diagnostic: &Diagnostic) -> Report<'_, Span> {
+    let kind = match diagnostic.severity {
+        Severity::Error => ReportKind::Error,
+        Severity::Warning => ReportKind::Warning,
+    };
+    let mut report = Report::build(kind, diagnostic.span)
+        .with_config(Config::default().with_index_type(IndexType::Byte))
+        .with_message(&diagnostic.message);
+    if let Some(code) = diagnostic.code {
+        report = report.with_code(code);
+    }
+    for label in &diagnostic.labels {
+        let mut ariadne_label = ariadne::Label::new(label.span);
+        match (&label.message, label.color) {
+            (Some(message), Some(color)) => {
+                ariadne_label = ariadne_label.with_message(message.fg(color))
+            }
+            (Some(message), None) => ariadne_label = ariadne_label.with_message(message),
+            (None, _) => {}
+        }
+        if let Some(color) = label.color {
+            ariadne_label = ariadne_label.with_color(color);
+        }
+        report = report.with_label(ariadne_label);
+    }
+    for note in &diagnostic.notes {
         report = report.with_note(note);
     }
-    report
-        .finish()
-        .print((span.src(), Source::from(code)))
-        .unwrap();
+    if !diagnostic.helps.is_empty() {
+        report = report.with_help(diagnostic.helps.join("\n"));
+    }
+    report.finish()
+}
+
+fn severity(diagnostic: &Diagnostic) -> &'static str {
+    match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    }
+}
+
+/// Returns the byte offset clamped to the source, 1-based line and 1-based column in chars of a
+/// byte offset.
+fn locate(db: &SourceDb, src: SrcId, offset: usize) -> (usize, usize, usize) {
+    let Some(index) = db.line_index(src) else {
+        return (0, 1, 1);
+    };
+    let offset = offset.min(index.text().len());
+    let LineCol { line, column } = index.line_col(offset, Encoding::Char);
+    (offset, line, column)
+}
+
+fn to_json(diagnostic: &Diagnostic, db: &SourceDb) -> Value {
+    let span = |span: Span, label: Option<&String>| {
+        let (byte_start, line_start, column_start) = locate(db, span.src(), span.range().start);
+        let (byte_end, line_end, column_end) = locate(db, span.src(), span.range().end);
+        json!({
+            "file": span.src().to_string(),
+            "byte_start": byte_start,
+            "byte_end": byte_end,
+            "line_start": line_start,
+            "column_start": column_start,
+            "line_end": line_end,
+            "column_end": column_end,
+            "is_primary": span == diagnostic.span,
+            "label": label,
+        })
+    };
+
+    let mut spans = Vec::new();
+    if diagnostic
+        .labels
+        .iter()
+        .all(|label| label.span != diagnostic.span)
+    {
+        spans.push(span(diagnostic.span, None));
+    }
+    spans.extend(
+        diagnostic
+            .labels
+            .iter()
+            .map(|label| span(label.span, label.message.as_ref())),
+    );
+
+    json!({
+        "severity": severity(diagnostic),
+        "code": diagnostic.code,
+        "message": diagnostic.message,
+        "spans": spans,
+        "notes": diagnostic.notes,
+        "helps": diagnostic.helps,
+    })
+}
+
+/// Converts the path of `src` into a URI: a `file` URI for an absolute path, or a relative
+/// reference resolved against the working directory.
+fn to_uri(src: SrcId) -> String {
+    let path = src.path();
+    let mut uri = String::from(if path.has_root() { "file://" } else { "" });
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+fn to_sarif_location(db: &SourceDb, span: Span, message: Option<&String>) -> Value {
+    let (byte_start, line_start, column_start) = locate(db, span.src(), span.range().start);
+    let (byte_end, line_end, column_end) = locate(db, span.src(), span.range().end);
+    let mut location = json!({
+        "physicalLocation": {
+            "artifactLocation": {"uri": to_uri(span.src())},
+            "region": {
+                "startLine": line_start,
+                "startColumn": column_start,
+                "endLine": line_end,
+                "endColumn": column_end,
+                "byteOffset": byte_start,
+                "byteLength": byte_end - byte_start,
+            },
+        },
+    });
+    if let Some(message) = message {
+        location["message"] = json!({"text": message});
+    }
+    location
+}
+
+fn to_sarif_result(diagnostic: &Diagnostic, db: &SourceDb) -> Value {
+    let related: Vec<_> = diagnostic
+        .labels
+        .iter()
+        .filter(|label| label.span != diagnostic.span)
+        .map(|label| to_sarif_location(db, label.span, label.message.as_ref()))
+        .collect();
+    let mut result = json!({
+        "level": severity(diagnostic),
+        "message": {"text": diagnostic.message},
+        "locations": [to_sarif_location(db, diagnostic.span, None)],
+        "relatedLocations": related,
+    });
+    if let Some(code) = diagnostic.code {
+        result["ruleId"] = code.into();
+    }
+    if !diagnostic.notes.is_empty() || !diagnostic.helps.is_empty() {
+        result["properties"] = json!({
+            "notes": diagnostic.notes,
+            "helps": diagnostic.helps,
+        });
+    }
+    result
+}
+
+fn to_sarif(results: Vec<Value>) -> Value {
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "matc",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            },
+            // `locate` counts columns in chars, while SARIF defaults to UTF-16 code units.
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diag::Label;
+    use crate::sema;
+    use matc_ast::Stmt;
+    use std::ops::Deref;
+
+    const CODE: &str = "let x: i32 = 1; // é\nlet x: i32 = 2;\n";
+
+    fn db() -> SourceDb {
+        SourceDb::new().with_source(SrcId::from_path("main.mat"), CODE.to_string())
+    }
+
+    /// The duplicated definition of `x`
+    fn diagnostic() -> Diagnostic {
+        let (_, diagnostics) = sema::check(
+            matc_parser::parse("main.mat", CODE).unwrap(),
+            LintLevels::new(),
+        );
+        let error = diagnostics.into_iter().find(Diagnostic::is_error).unwrap();
+        error.with_note("a note")
+    }
+
+    #[test]
+    fn test_emit_order() {
+        let db = db();
+        let span = |start| Span::new(SrcId::from_path("main.mat"), start..start + 1);
+        let diagnostics = vec![
+            Diagnostic::warning(span(0), "w0"),
+            Diagnostic::error(span(20), "e20"),
+            Diagnostic::warning(span(10), "w10"),
+            Diagnostic::error(span(5), "e5"),
+        ];
+        let mut out = Vec::new();
+        let mut emitter = Emitter::new(ErrorFormat::Json, &db).with_output(&mut out);
+        emitter.emit(&diagnostics);
+        emitter.finish();
+        let messages: Vec<_> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["message"].clone())
+            .collect();
+        assert_eq!(messages, vec!["e5", "e20", "w0", "w10"]);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let json = to_json(&diagnostic(), &db());
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["code"], "E0101");
+        assert_eq!(json["message"], "Duplicated definition");
+        assert_eq!(json["notes"], json!(["a note"]));
+
+        let spans = json["spans"].as_array().unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(
+            spans[0],
+            json!({
+                "file": "main.mat",
+                "byte_start": 4,
+                "byte_end": 5,
+                "line_start": 1,
+                "column_start": 5,
+                "line_end": 1,
+                "column_end": 6,
+                "is_primary": false,
+                "label": "previous definition",
+            })
+        );
+        assert_eq!(spans[1]["is_primary"], true);
+        assert_eq!(spans[1]["byte_start"], 26);
+        assert_eq!(spans[1]["line_start"], 2);
+        assert_eq!(spans[1]["column_start"], 5);
+        assert_eq!(spans[1]["label"], "redefined here");
+    }
+
+    #[test]
+    fn test_to_sarif() {
+        let sarif = to_sarif(vec![to_sarif_result(&diagnostic(), &db())]);
+        assert_eq!(sarif["version"], "2.1.0");
+
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "matc");
+        assert_eq!(run["columnKind"], "unicodeCodePoints");
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "E0101");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"],
+            json!({
+                "startLine": 2,
+                "startColumn": 5,
+                "endLine": 2,
+                "endColumn": 6,
+                "byteOffset": 26,
+                "byteLength": 1,
+            })
+        );
+        let related = result["relatedLocations"].as_array().unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0]["message"]["text"], "previous definition");
+        assert_eq!(result["properties"]["notes"], json!(["a note"]));
+    }
+
+    #[test]
+    fn test_sarif_columns() {
+        // `😀` is a single code point but a surrogate pair in UTF-16.
+        let code = "/* 😀 */ let x: i32 = 1;\n";
+        let db = SourceDb::new().with_source(SrcId::from_path("main.mat"), code.to_string());
+        let (_, diagnostics) = sema::check(
+            matc_parser::parse("main.mat", code).unwrap(),
+            LintLevels::new(),
+        );
+        let result = to_sarif_result(&diagnostics[0], &db);
+        let region = &result["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startColumn"], 13);
+        assert_eq!(region["endColumn"], 14);
+    }
+
+    #[test]
+    fn test_uri() {
+        assert_eq!(to_uri(SrcId::from_path("main.mat")), "main.mat");
+        assert_eq!(to_uri(SrcId::from_path("src/é.mat")), "src/%C3%A9.mat");
+        assert_eq!(
+            to_uri(SrcId::from_path("/tmp/a b/w.mat")),
+            "file:///tmp/a%20b/w.mat"
+        );
+
+        let code = "let x: i32 = 1;\n";
+        let path = "/tmp/t/w.mat";
+        let db = SourceDb::new().with_source(SrcId::from_path(path), code.to_string());
+        let ast = matc_parser::parse(path, code).unwrap();
+        let (_, diagnostics) = sema::check(ast, LintLevels::new());
+        let result = to_sarif_result(&diagnostics[0], &db);
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "file:///tmp/t/w.mat"
+        );
+        assert_eq!(to_json(&diagnostics[0], &db)["spans"][0]["file"], path);
+    }
+
+    #[test]
+    fn test_multiple_sources() {
+        let mut db = SourceDb::new();
+        let mut name = |path: &str, code: &str| {
+            db.insert(SrcId::from_path(path), code.to_string());
+            let ast = matc_parser::parse(path, code).unwrap();
+            let Stmt::DefVar { name, .. } = ast.defs[0].deref() else {
+                unreachable!()
+            };
+            name.span
+        };
+        let previous = name("lib.mat", "let x: i32 = 1;\n");
+        let span = name("main.mat", "\nlet x: i32 = 2;\n");
+        let diagnostic = Diagnostic::error(span, "Duplicated definition")
+            .with_label(Label::new(previous).with_message("previous definition"))
+            .with_label(Label::new(span).with_message("redefined here"));
+
+        let mut out = Vec::new();
+        report(&diagnostic).write(&db, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("main.mat:2:5"));
+        assert!(out.contains("lib.mat:1:5"));
+
+        let spans = &to_json(&diagnostic, &db)["spans"];
+        assert_eq!(spans[0]["file"], "lib.mat");
+        assert_eq!(spans[0]["line_start"], 1);
+        assert_eq!(spans[1]["file"], "main.mat");
+        assert_eq!(spans[1]["line_start"], 2);
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        // Errors at the end of input without a trailing newline
+        let code = "fn main() -> i32 { return 1 +";
+        let db = SourceDb::new().with_source(SrcId::from_path("main.mat"), code.to_string());
+        let errors = matc_parser::parse("main.mat", code).unwrap_err();
+        let mut out = Vec::new();
+        for diagnostic in errors.diagnostics() {
+            assert!(diagnostic.span.range().end <= code.len());
+            report(&diagnostic).write(&db, &mut out).unwrap();
+        }
+        assert!(String::from_utf8(out).unwrap().contains("main.mat:1:30"));
+    }
 }
@@ -0,0 +1,192 @@
+/// A long-form explanation of a diagnostic code for `--explain`
+pub(crate) struct Explanation {
+    pub(crate) code: &'static str,
+    pub(crate) title: &'static str,
+    pub(crate) description: &'static str,
+    /// A program which produces the diagnostic
+    pub(crate) failing: &'static str,
+    /// `failing` fixed not to produce the diagnostic
+    pub(crate) corrected: &'static str,
+}
+
+pub(crate) const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "E0001",
+        title: "Unexpected token",
+        description: "The parser found a token or the end of input where it is not allowed.",
+        failing: "fn f() -> i32 {\n    return 1\n}\n",
+        corrected: "fn f() -> i32 {\n    return 1;\n}\n",
+    },
+    Explanation {
+        code: "E0002",
+        title: "Unclosed delimiter",
+        description: "A delimiter such as `{` is not closed before the end of input.",
+        failing: "fn f() -> void {\n",
+        corrected: "fn f() -> void {}\n",
+    },
+    Explanation {
+        code: "E0003",
+        title: "Invalid literal",
+        description: "A literal is malformed, e.g. an integer literal does not fit in its type.",
+        failing: "fn f() -> i32 {\n    return 4294967296;\n}\n",
+        corrected: "fn f() -> i32 {\n    return 2147483647;\n}\n",
+    },
+    Explanation {
+        code: "E0004",
+        title: "Unknown type",
+        description: "A type annotation names a type which does not exist. \
+            The available types are `void`, `char`, `i32` and function types such as \
+            `fn(i32, char) -> i32`.",
+        failing: "fn f() -> i23 {\n    return 1;\n}\n",
+        corrected: "fn f() -> i32 {\n    return 1;\n}\n",
+    },
+    Explanation {
+        code: "E0101",
+        title: "Duplicated definition",
+        description: "An entity is defined more than once in the same scope.",
+        failing: "fn f() -> void {}\nfn f() -> void {}\n",
+        corrected: "fn f() -> void {}\nfn g() -> void {}\n",
+    },
+    Explanation {
+        code: "E0102",
+        title: "Unresolved reference",
+        description: "An identifier refers to nothing defined in its scope or any enclosing scope.",
+        failing: "fn f() -> i32 {\n    return x;\n}\n",
+        corrected: "fn f(x: i32) -> i32 {\n    return x;\n}\n",
+    },
+    Explanation {
+        code: "E0103",
+        title: "Not a constant",
+        description: "A toplevel variable is initialized with something other than a literal \
+            or a function. \
+            Toplevel definitions are evaluated before any function runs, so they should be \
+            constants.",
+        failing: "let x: i32 = 1 + 1;\nfn f() -> i32 {\n    return x;\n}\n",
+        corrected: "let x: i32 = 2;\nfn f() -> i32 {\n    return x;\n}\n",
+    },
+    Explanation {
+        code: "E0104",
+        title: "Not callable",
+        description: "Something other than a function is called.",
+        failing: "let g: i32 = 1;\nfn f() -> i32 {\n    let y: i32 = g(1);\n    return y;\n}\n",
+        corrected:
+            "fn g(x: i32) -> i32 {\n    return x;\n}\nfn f() -> i32 {\n    let y: i32 = g(1);\n    return y;\n}\n",
+    },
+    Explanation {
+        code: "E0105",
+        title: "Missing return",
+        description: "A function with a return type other than `void` may reach the end of its \
+            body without returning a value.",
+        failing: "fn f(x: i32) -> i32 {\n    if x {\n        return 1;\n    }\n}\n",
+        corrected:
+            "fn f(x: i32) -> i32 {\n    if x {\n        return 1;\n    }\n    return 0;\n}\n",
+    },
+    Explanation {
+        code: "E0106",
+        title: "Argument count mismatch",
+        description: "A function is called with more or fewer arguments than its parameters.",
+        failing: "fn g(x: i32) -> i32 {\n    return x;\n}\nfn f() -> i32 {\n    return g(1, 2);\n}\n",
+        corrected: "fn g(x: i32) -> i32 {\n    return x;\n}\nfn f() -> i32 {\n    return g(1);\n}\n",
+    },
+    Explanation {
+        code: "E0107",
+        title: "Type mismatch",
+        description: "A value is stored, passed, returned or converted where its type does \
+            not fit. Integers convert into each other, but `void` results, string literals and \
+            functions do not; functions fit only where the signature is the same.",
+        failing: "fn g(x: i32) -> i32 {\n    return x;\n}\nfn f() -> i32 {\n    let h: fn(char) -> i32 = g;\n    return h(1);\n}\n",
+        corrected: "fn g(x: i32) -> i32 {\n    return x;\n}\nfn f() -> i32 {\n    let h: fn(i32) -> i32 = g;\n    return h(1);\n}\n",
+    },
+    Explanation {
+        code: "E0108",
+        title: "Builtin used as a value",
+        description: "A builtin function such as `putchar` is used other than being called. \
+            Builtins have no signatures, so they cannot be stored in variables or passed as \
+            arguments; wrap them in a function instead.",
+        failing: "fn f() -> i32 {\n    let h: fn(i32) -> i32 = putchar;\n    return h(65);\n}\n",
+        corrected: "fn put(c: i32) -> i32 {\n    return putchar(c);\n}\nfn f() -> i32 {\n    let h: fn(i32) -> i32 = put;\n    return h(65);\n}\n",
+    },
+    Explanation {
+        code: "W0001",
+        title: "Unused entity",
+        description: "A variable or function other than `main` is defined but never referred \
+            to.",
+        failing: "fn main() -> i32 {\n    let x: i32 = 1;\n    return 1;\n}\n",
+        corrected: "fn main() -> i32 {\n    let x: i32 = 1;\n    return x;\n}\n",
+    },
+    Explanation {
+        code: "W0002",
+        title: "Unreachable code",
+        description: "A statement follows a `return`, so it can never be executed.",
+        failing: "fn f() -> i32 {\n    return 1;\n    return 2;\n}\n",
+        corrected: "fn f() -> i32 {\n    return 1;\n}\n",
+    },
+    Explanation {
+        code: "W0003",
+        title: "Unknown lint",
+        description: "An attribute names a lint which does not exist. Every lint can be \
+            controlled with `allow`, `warn` or `deny`, and `warnings` names all of them.",
+        failing: "#[allow(unusde)]\nfn main() -> i32 {\n    let x: i32 = 1;\n    return 0;\n}\n",
+        corrected: "#[allow(unused)]\nfn main() -> i32 {\n    let x: i32 = 1;\n    return 0;\n}\n",
+    },
+    Explanation {
+        code: "W0004",
+        title: "Confusable identifier",
+        description: "Two definitions have different names which look the same, e.g. because \
+            one spells `a` with the Cyrillic letter `а`. Such names are easy to mix up when \
+            reading the code.",
+        failing: "fn f(a: i32) -> i32 {\n    let а: i32 = 1;\n    return a;\n}\n",
+        corrected: "fn f(a: i32) -> i32 {\n    let b: i32 = 1;\n    return a + b;\n}\n",
+    },
+];
+
+/// Returns the explanation of `code` formatted for a terminal, e.g. `E0101`.
+pub(crate) fn explain(code: &str) -> Option<String> {
+    let explanation = EXPLANATIONS
+        .iter()
+        .find(|explanation| explanation.code.eq_ignore_ascii_case(code))?;
+    Some(format!(
+        "{}: {}\n\n{}\n\nErroneous code example:\n\n```mat\n{}```\n\nCorrected:\n\n```mat\n{}```",
+        explanation.code,
+        explanation.title,
+        explanation.description,
+        explanation.failing,
+        explanation.corrected,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diag::lint::LintLevels;
+    use crate::diag::Emit;
+    use crate::sema;
+
+    fn codes(code: &str) -> Vec<&'static str> {
+        let diagnostics = match matc_parser::parse("test.mat", code) {
+            Ok(ast) => sema::check(ast, LintLevels::new()).1,
+            Err(errors) => errors.diagnostics(),
+        };
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.code.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_examples() {
+        for explanation in EXPLANATIONS {
+            let code = explanation.code;
+            assert!(codes(explanation.failing).contains(&code), "{}", code);
+            assert!(!codes(explanation.corrected).contains(&code), "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        assert!(explain("e0101")
+            .unwrap()
+            .starts_with("E0101: Duplicated definition\n"));
+        assert_eq!(explain("E9999"), None);
+    }
+}
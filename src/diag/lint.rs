@@ -0,0 +1,163 @@
+use crate::diag::{Diagnostic, Severity};
+use matc_span::Span;
+
+/// The lint group containing every lint
+pub(crate) const WARNINGS: &str = "warnings";
+
+pub(crate) const LINTS: &[&str] = &[
+    "unused",
+    "unreachable_code",
+    "unknown_lints",
+    "confusable_idents",
+];
+
+pub(crate) fn is_known(lint: &str) -> bool {
+    lint == WARNINGS || LINTS.contains(&lint)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// Overrides of lint levels, either from the command line or scoped to a definition
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LintLevels {
+    /// The span is `None` for the command line
+    levels: Vec<(Option<Span>, String, Level)>,
+}
+
+impl LintLevels {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn push(&mut self, span: Option<Span>, lint: String, level: Level) {
+        self.levels.push((span, lint, level));
+    }
+
+    /// Returns the level of `lint` at `span`; the innermost definition wins, and later ones win
+    /// on a tie.
+    fn level(&self, lint: &str, span: Span) -> Level {
+        self.levels
+            .iter()
+            .rev()
+            .filter(|(_, name, _)| name == lint || name == WARNINGS)
+            .filter_map(|(scope, _, level)| match scope {
+                None => Some((usize::MAX, *level)),
+                Some(scope) if contains(*scope, span) => Some((scope.range().len(), *level)),
+                Some(_) => None,
+            })
+            .min_by_key(|(len, _)| *len)
+            .map_or(Level::Warn, |(_, level)| level)
+    }
+
+    /// Drops allowed lints and promotes denied ones to errors.
+    pub(crate) fn apply(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics
+            .into_iter()
+            .filter_map(|diagnostic| {
+                let Some(lint) = diagnostic.lint else {
+                    return Some(diagnostic);
+                };
+                match self.level(lint, diagnostic.span) {
+                    Level::Allow => None,
+                    Level::Warn => Some(diagnostic),
+                    Level::Deny => Some(Diagnostic {
+                        severity: Severity::Error,
+                        ..diagnostic.with_note(format!("`{}` is denied", lint))
+                    }),
+                }
+            })
+            .collect()
+    }
+}
+
+fn contains(outer: Span, inner: Span) -> bool {
+    outer.src() == inner.src()
+        && outer.range().start <= inner.range().start
+        && inner.range().end <= outer.range().end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diag::Emit;
+    use crate::sema;
+
+    fn levels(code: &str, levels: LintLevels) -> Vec<(&'static str, Severity)> {
+        let ast = matc_parser::parse("test.mat", code).unwrap();
+        sema::check(ast, levels)
+            .1
+            .into_iter()
+            .map(|diagnostic| (diagnostic.code.unwrap(), diagnostic.severity))
+            .collect()
+    }
+
+    fn cli(levels: &[(&str, Level)]) -> LintLevels {
+        let mut lint_levels = LintLevels::new();
+        for (lint, level) in levels {
+            lint_levels.push(None, lint.to_string(), *level);
+        }
+        lint_levels
+    }
+
+    const CODE: &str =
+        "let x: i32 = 1;\nfn main() -> i32 {\n    let y: i32 = 1;\n    return 0;\n}\n";
+
+    #[test]
+    fn test_command_line() {
+        use Severity::*;
+        assert_eq!(
+            levels(CODE, LintLevels::new()),
+            vec![("W0001", Warning), ("W0001", Warning)]
+        );
+        assert_eq!(levels(CODE, cli(&[("unused", Level::Allow)])), vec![]);
+        assert_eq!(
+            levels(CODE, cli(&[("warnings", Level::Deny)])),
+            vec![("W0001", Error), ("W0001", Error)]
+        );
+        // Later flags win
+        assert_eq!(
+            levels(
+                CODE,
+                cli(&[("warnings", Level::Deny), ("unused", Level::Warn)])
+            ),
+            vec![("W0001", Warning), ("W0001", Warning)]
+        );
+    }
+
+    #[test]
+    fn test_attributes() {
+        use Severity::*;
+        let code = "#[allow(unused)]\nlet x: i32 = 1;\n#[deny(warnings)]\nfn main() -> i32 {\n    #[warn(unused)]\n    let y: i32 = 1;\n    let z: i32 = 1;\n    return 0;\n}\n";
+        assert_eq!(
+            levels(code, LintLevels::new()),
+            vec![("W0001", Warning), ("W0001", Error)]
+        );
+        // Attributes take precedence over the command line
+        assert_eq!(
+            levels(code, cli(&[("unused", Level::Allow)])),
+            vec![("W0001", Warning), ("W0001", Error)]
+        );
+        assert_eq!(
+            levels("#[allow(unusde)]\nlet x: i32 = 1;\n", LintLevels::new()),
+            vec![("W0003", Warning), ("W0001", Warning)]
+        );
+    }
+
+    #[test]
+    fn test_count() {
+        let diagnostics = |code| {
+            let ast = matc_parser::parse("test.mat", code).unwrap();
+            sema::check(ast, cli(&[("unused", Level::Deny)])).1
+        };
+        assert_eq!(diagnostics(CODE).count(), 2);
+        assert_eq!(
+            diagnostics("fn main() -> i32 {\n    return 0;\n}\n").count(),
+            0
+        );
+    }
+}
@@ -1,12 +1,12 @@
-use crate::diag::{emit, Emit};
-use ariadne::{Color, Fmt, Label};
-use matc_parser::error::{Error, SimpleReason};
+use crate::diag::{Diagnostic, Emit, Label};
+use ariadne::Color;
+use matc_parser::error::{Error, SimpleReason, Unknown};
 
 impl Emit for Error {
-    fn emit(&self, code: &str) {
-        let span = self.span();
-        let (message, labels) = match self.reason() {
-            SimpleReason::Unexpected => (
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let diagnostic = match self.reason() {
+            SimpleReason::Unexpected => Diagnostic::error(
+                self.span(),
                 format!(
                     "{}{}, expected {}",
                     if self.found().is_some() {
@@ -15,7 +15,7 @@ impl Emit for Error {
                         "unexpected end of input"
                     },
                     if let Some(label) = self.label() {
-                        format!(" while parsing {}", label.fg(Color::Green))
+                        format!(" while parsing {}", label)
                     } else {
                         " something else".to_string()
                     },
@@ -31,40 +31,47 @@ impl Emit for Error {
                             .join(", ")
                     }
                 ),
-                vec![Label::new(self.span()).with_message(format!(
-                    "Unexpected {}",
-                    self.found()
-                        .map(|c| format!("token {}", c.fg(Color::Red)))
-                        .unwrap_or_else(|| "end of input".to_string())
-                ))],
-            ),
-            SimpleReason::Unclosed { span, delimiter } => (
-                format!("Unclosed delimiter {}", delimiter.fg(Color::Yellow)),
-                vec![
-                    Label::new(*span)
-                        .with_message(format!(
-                            "Unclosed delimiter {}",
-                            delimiter.fg(Color::Yellow)
-                        ))
-                        .with_color(Color::Yellow),
-                    Label::new(self.span())
-                        .with_message(format!(
-                            "Must be closed before this {}",
-                            self.found()
-                                .map(|found| found.to_string())
-                                .unwrap_or_else(|| "end of input".to_string())
-                                .fg(Color::Red)
-                        ))
-                        .with_color(Color::Red),
-                ],
-            ),
-            SimpleReason::Custom(msg) => (
-                msg.clone(),
-                vec![Label::new(self.span())
-                    .with_message(format!("{}", msg.fg(Color::Red)))
-                    .with_color(Color::Red)],
+            )
+            .with_label(Label::new(self.span()).with_message(format!(
+                "Unexpected {}",
+                self.found()
+                    .map(|c| format!("token {}", c))
+                    .unwrap_or_else(|| "end of input".to_string())
+            ))),
+            SimpleReason::Unclosed { span, delimiter } => {
+                Diagnostic::error(self.span(), format!("Unclosed delimiter {}", delimiter))
+                    .with_label(
+                        Label::new(*span)
+                            .with_message(format!("Unclosed delimiter {}", delimiter))
+                            .with_color(Color::Yellow),
+                    )
+                    .with_label(
+                        Label::new(self.span())
+                            .with_message(format!(
+                                "Must be closed before this {}",
+                                self.found()
+                                    .map(|found| found.to_string())
+                                    .unwrap_or_else(|| "end of input".to_string())
+                            ))
+                            .with_color(Color::Red),
+                    )
+            }
+            SimpleReason::Custom(msg) => Diagnostic::error(self.span(), msg).with_label(
+                Label::new(self.span())
+                    .with_message(msg)
+                    .with_color(Color::Red),
             ),
         };
-        emit(code, span, message, labels, vec![]);
+        let code = match (self.unknown(), self.reason()) {
+            (Some(Unknown::Type), _) => "E0004",
+            (Some(Unknown::Keyword), _) | (None, SimpleReason::Unexpected) => "E0001",
+            (None, SimpleReason::Unclosed { .. }) => "E0002",
+            (None, SimpleReason::Custom(_)) => "E0003",
+        };
+        let diagnostic = diagnostic.with_code(code);
+        vec![match self.help() {
+            Some(help) => diagnostic.with_help(help),
+            None => diagnostic,
+        }]
     }
 }
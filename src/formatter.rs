@@ -0,0 +1,549 @@
+use matc_ast::{Ast, Attribute, Comment, Expr, Param, Stmt};
+use matc_span::Spanned;
+use std::ops::{Deref, Range};
+
+const INDENT: &str = "    ";
+
+/// Pretty-prints `ast` parsed from `code` in the canonical style, keeping comments.
+///
+/// Comments are kept in `Ast::comments` with their spans and placed by position within the
+/// innermost block containing them: those between statements go on their own lines before the
+/// next one, block comments in an expression stay before the operand following them, and the
+/// other ones inside a statement move to the end of its last line.
+pub(crate) fn format(ast: &Ast, code: &str) -> String {
+    let mut formatter = Formatter {
+        code,
+        comments: ast.comments.iter().map(Some).collect(),
+        out: String::new(),
+        indent: 0,
+        stmt_start: 0,
+    };
+    formatter.stmts(&ast.defs, 0..formatter.code.len(), true);
+    formatter.out
+}
+
+/// Binding power of expressions following the precedence ladder of `matc_parser::expr`
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Or(..) => 1,
+        Expr::And(..) => 2,
+        Expr::Lt(..)
+        | Expr::Gt(..)
+        | Expr::Lte(..)
+        | Expr::Gte(..)
+        | Expr::Eq(..)
+        | Expr::Neq(..) => 3,
+        Expr::BitOr(..) => 4,
+        Expr::BitXor(..) => 5,
+        Expr::BitAnd(..) => 6,
+        Expr::Shl(..) | Expr::Shr(..) => 7,
+        Expr::Add(..) | Expr::Sub(..) => 8,
+        Expr::Mul(..) | Expr::Div(..) | Expr::Rem(..) => 9,
+        Expr::As(..) => 10,
+        Expr::FnCall { .. } => 11,
+        Expr::I8(_) | Expr::I32(_) | Expr::String(_) | Expr::Variable(_) | Expr::Error => 12,
+    }
+}
+
+fn binary(expr: &Expr) -> Option<(&Spanned<Expr>, &'static str, &Spanned<Expr>)> {
+    let (lhs, op, rhs) = match expr {
+        Expr::Or(lhs, rhs) => (lhs, "||", rhs),
+        Expr::And(lhs, rhs) => (lhs, "&&", rhs),
+        Expr::Lt(lhs, rhs) => (lhs, "<", rhs),
+        Expr::Gt(lhs, rhs) => (lhs, ">", rhs),
+        Expr::Lte(lhs, rhs) => (lhs, "<=", rhs),
+        Expr::Gte(lhs, rhs) => (lhs, ">=", rhs),
+        Expr::Eq(lhs, rhs) => (lhs, "==", rhs),
+        Expr::Neq(lhs, rhs) => (lhs, "!=", rhs),
+        Expr::BitOr(lhs, rhs) => (lhs, "|", rhs),
+        Expr::BitXor(lhs, rhs) => (lhs, "^", rhs),
+        Expr::BitAnd(lhs, rhs) => (lhs, "&", rhs),
+        Expr::Shl(lhs, rhs) => (lhs, "<<", rhs),
+        Expr::Shr(lhs, rhs) => (lhs, ">>", rhs),
+        Expr::Add(lhs, rhs) => (lhs, "+", rhs),
+        Expr::Sub(lhs, rhs) => (lhs, "-", rhs),
+        Expr::Mul(lhs, rhs) => (lhs, "*", rhs),
+        Expr::Div(lhs, rhs) => (lhs, "/", rhs),
+        Expr::Rem(lhs, rhs) => (lhs, "%", rhs),
+        _ => return None,
+    };
+    Some((lhs, op, rhs))
+}
+
+fn param(param: &Param) -> String {
+    format!(
+        "{}{}: {}",
+        if param.is_mut { "mut " } else { "" },
+        param.name.value,
+        param.ty.value
+    )
+}
+
+struct Formatter<'a> {
+    code: &'a str,
+    // Taken out once printed
+    comments: Vec<Option<&'a Spanned<Comment>>>,
+    out: String,
+    indent: usize,
+    /// The start of the statement being printed, before which no comment is taken inline
+    stmt_start: usize,
+}
+
+impl<'a> Formatter<'a> {
+    /// Prints statements in `range` of the code, each on its own lines along with the comments
+    /// in between.
+    fn stmts(&mut self, stmts: &[Spanned<Stmt>], range: Range<usize>, toplevel: bool) {
+        let mut prev: Option<(usize, bool)> = None; // (end, is a function)
+        for stmt in stmts {
+            if let Stmt::Empty = stmt.deref() {
+                continue;
+            }
+            let is_fn = matches!(stmt.deref(), Stmt::DefFn { .. });
+            let leading = self
+                .take_comments(prev.map_or(range.start, |(end, _)| end)..stmt.span.range().start);
+
+            if let Some((end, prev_is_fn)) = prev {
+                let start = leading
+                    .first()
+                    .map_or(stmt.span.range().start, |c| c.span.range().start);
+                // Separate functions by a blank line, otherwise keep at most one.
+                if (toplevel && (prev_is_fn || is_fn)) || self.has_blank_line(end..start) {
+                    self.out.push('\n');
+                }
+            }
+            self.own_line_comments(&leading);
+            if let Some(last) = leading.last() {
+                if self.has_blank_line(last.span.range().end..stmt.span.range().start) {
+                    self.out.push('\n');
+                }
+            }
+
+            self.line_start();
+            self.stmt_start = stmt.span.range().start;
+            self.stmt(stmt);
+            // Comments after the block on the same line belong to the enclosing statement.
+            let end = self.line_end(stmt.span.range().end).min(range.end);
+            self.trailing_comments(stmt.span.range().start..end);
+            self.out.push('\n');
+            prev = Some((stmt.span.range().end, is_fn));
+        }
+
+        let start = prev.map_or(range.start, |(end, _)| end);
+        let rest = self.take_comments(start..range.end);
+        if let (Some(first), Some(_)) = (rest.first(), prev) {
+            if self.has_blank_line(start..first.span.range().start) {
+                self.out.push('\n');
+            }
+        }
+        self.own_line_comments(&rest);
+    }
+
+    /// Prints attributes each on its own line before a definition.
+    fn attrs(&mut self, attrs: &[Spanned<Attribute>]) {
+        for attr in attrs {
+            self.out.push_str(&attr.to_string());
+            self.out.push('\n');
+            self.line_start();
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Spanned<Stmt>) {
+        match stmt.deref() {
+            Stmt::Empty => {}
+            Stmt::DefFn {
+                attrs,
+                name,
+                args,
+                ret_ty,
+                body,
+            } => {
+                self.attrs(attrs);
+                self.out.push_str(&format!(
+                    "fn {}({}) -> {} ",
+                    name.value,
+                    args.iter().map(param).collect::<Vec<_>>().join(", "),
+                    ret_ty.value
+                ));
+                self.stmt(body);
+            }
+            Stmt::DefVar {
+                attrs,
+                is_mut,
+                name,
+                ty,
+                expr,
+            } => {
+                self.attrs(attrs);
+                self.out.push_str(&format!(
+                    "let {}{}: {}",
+                    if *is_mut { "mut " } else { "" },
+                    name.value,
+                    ty.value
+                ));
+                if let Some(init) = expr {
+                    let init = self.expr(init);
+                    self.out.push_str(&format!(" = {}", init));
+                }
+                self.out.push(';');
+            }
+            Stmt::Block(stmts) => {
+                let inner = stmt.span.range().start + 1..stmt.span.range().end.saturating_sub(1);
+                if stmts.iter().all(|s| matches!(s.deref(), Stmt::Empty))
+                    && !self.has_comments(inner.clone())
+                {
+                    self.out.push_str("{}");
+                    return;
+                }
+                self.out.push_str("{\n");
+                self.indent += 1;
+                self.stmts(stmts, inner, false);
+                self.indent -= 1;
+                self.line_start();
+                self.out.push('}');
+            }
+            Stmt::If { cond, then, els } => {
+                let cond = self.expr(cond);
+                self.out.push_str(&format!("if {} ", cond));
+                self.stmt(then);
+                if let Some(els) = els {
+                    // `else if` chains stay flat.
+                    self.out.push_str(" else ");
+                    self.stmt(els);
+                }
+            }
+            Stmt::Return(None) => self.out.push_str("return;"),
+            Stmt::Return(Some(value)) => {
+                let value = self.expr(value);
+                self.out.push_str(&format!("return {};", value));
+            }
+            Stmt::Assign(lhs, rhs) => self.assign(lhs, "=", rhs),
+            Stmt::AddAssign(lhs, rhs) => self.assign(lhs, "+=", rhs),
+            Stmt::SubAssign(lhs, rhs) => self.assign(lhs, "-=", rhs),
+            Stmt::MulAssign(lhs, rhs) => self.assign(lhs, "*=", rhs),
+            Stmt::DivAssign(lhs, rhs) => self.assign(lhs, "/=", rhs),
+            Stmt::RemAssign(lhs, rhs) => self.assign(lhs, "%=", rhs),
+            Stmt::BitAndAssign(lhs, rhs) => self.assign(lhs, "&=", rhs),
+            Stmt::BitOrAssign(lhs, rhs) => self.assign(lhs, "|=", rhs),
+            Stmt::BitXorAssign(lhs, rhs) => self.assign(lhs, "^=", rhs),
+            Stmt::ShlAssign(lhs, rhs) => self.assign(lhs, "<<=", rhs),
+            Stmt::ShrAssign(lhs, rhs) => self.assign(lhs, ">>=", rhs),
+            Stmt::Expr(value) => {
+                let value = self.expr(value);
+                self.out.push_str(&format!("{};", value));
+            }
+            Stmt::Error => unreachable!("must not happen: only parsed code is formatted"),
+        }
+    }
+
+    fn assign(&mut self, lhs: &Spanned<Expr>, op: &str, rhs: &Spanned<Expr>) {
+        let (lhs, rhs) = (self.expr(lhs), self.expr(rhs));
+        self.out.push_str(&format!("{} {} {};", lhs, op, rhs));
+    }
+
+    /// Formats `expr` with the block comments before its operands in the current statement.
+    fn expr(&mut self, expr: &Spanned<Expr>) -> String {
+        // The spans of names include the comments padding them.
+        let start = self.skip_trivia(expr.span.range().start);
+        let comments = self.inline_comments(start);
+        comments + &self.bare_expr(expr)
+    }
+
+    fn bare_expr(&mut self, expr: &Spanned<Expr>) -> String {
+        // Every operator is left-associative, so only the right operand needs parentheses on a
+        // tie.
+        let mut operand = |operand: &Spanned<Expr>, min: u8| {
+            if precedence(operand) < min {
+                format!("({})", self.expr(operand))
+            } else {
+                self.expr(operand)
+            }
+        };
+
+        if let Some((lhs, op, rhs)) = binary(expr) {
+            let prec = precedence(expr);
+            let lhs = operand(lhs, prec);
+            return format!("{} {} {}", lhs, op, operand(rhs, prec + 1));
+        }
+        match expr.deref() {
+            Expr::As(lhs, ty) => format!("{} as {}", operand(lhs, precedence(expr)), ty.value),
+            Expr::FnCall { name, args } => {
+                let name = operand(name, precedence(expr));
+                let args = args.iter().map(|arg| self.expr(arg)).collect::<Vec<_>>();
+                format!("{}({})", name, args.join(", "))
+            }
+            Expr::I8(c) => format!("'{}'", (*c as u8 as char).escape_debug()),
+            Expr::I32(n) => n.to_string(),
+            Expr::String(s) => format!("\"{}\"", s.escape_debug()),
+            Expr::Variable(name) => name.clone(),
+            Expr::Error => unreachable!("must not happen: only parsed code is formatted"),
+            _ => unreachable!("binary operators are handled above"),
+        }
+    }
+
+    fn line_start(&mut self) {
+        self.out.push_str(&INDENT.repeat(self.indent));
+    }
+
+    /// Returns the end of the line containing `offset`.
+    fn line_end(&self, offset: usize) -> usize {
+        self.code[offset..]
+            .find('\n')
+            .map_or(self.code.len(), |i| offset + i)
+    }
+
+    /// Returns the offset of the first token from `offset`, skipping whitespace and comments.
+    fn skip_trivia(&self, mut offset: usize) -> usize {
+        loop {
+            let rest = &self.code[offset..];
+            let trimmed = rest.trim_start();
+            offset += rest.len() - trimmed.len();
+            let end = if trimmed.starts_with("/*") {
+                trimmed.find("*/").map(|i| i + 2)
+            } else if trimmed.starts_with("//") {
+                trimmed.find('\n')
+            } else {
+                None
+            };
+            match end {
+                Some(end) => offset += end,
+                None => return offset,
+            }
+        }
+    }
+
+    fn has_blank_line(&self, range: Range<usize>) -> bool {
+        self.code[range].matches('\n').count() >= 2
+    }
+
+    fn has_comments(&self, range: Range<usize>) -> bool {
+        self.comments
+            .iter()
+            .flatten()
+            .any(|comment| range.contains(&comment.span.range().start))
+    }
+
+    fn take_comments(&mut self, range: Range<usize>) -> Vec<&'a Spanned<Comment>> {
+        self.comments
+            .iter_mut()
+            .filter(|comment| matches!(comment, Some(c) if range.contains(&c.span.range().start)))
+            .filter_map(Option::take)
+            .collect()
+    }
+
+    /// Prints comments on their own lines, keeping single blank lines between them.
+    fn own_line_comments(&mut self, comments: &[&Spanned<Comment>]) {
+        for (i, comment) in comments.iter().enumerate() {
+            if i > 0
+                && self.has_blank_line(comments[i - 1].span.range().end..comment.span.range().start)
+            {
+                self.out.push('\n');
+            }
+            self.line_start();
+            self.out.push_str(&comment.value.to_string());
+            self.out.push('\n');
+        }
+    }
+
+    /// Takes the block comments from the start of the current statement up to `end`, each
+    /// followed by a space.
+    fn inline_comments(&mut self, end: usize) -> String {
+        let range = self.stmt_start..end;
+        self.comments
+            .iter_mut()
+            .filter(|comment| {
+                matches!(comment, Some(c) if matches!(c.value(), Comment::Block(_))
+                    && range.contains(&c.span.range().start))
+            })
+            .filter_map(Option::take)
+            .map(|comment| format!("{} ", comment.value))
+            .collect()
+    }
+
+    /// Prints comments inside or just after a statement at the end of its last line.
+    fn trailing_comments(&mut self, range: Range<usize>) {
+        for comment in self.take_comments(range) {
+            self.out.push(' ');
+            self.out.push_str(&comment.value.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{read_dir, read_to_string};
+
+    /// Formats `code`, checking that the result is stable.
+    fn fmt(code: &str) -> String {
+        let formatted = format(&matc_parser::parse("test.mat", code).unwrap(), code);
+        let again = format(
+            &matc_parser::parse("test.mat", &formatted).unwrap(),
+            &formatted,
+        );
+        assert_eq!(again, formatted);
+        formatted
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            fmt(r#"let  mut x:i32=1+2*3 ; let y : char='a';
+fn add( a:i32,mut b :i32 )->i32{return a+b;}
+#[ allow( unused ,warnings ) ]   fn main()->i32{
+  #[deny(unused)] let s:i32=add((1),x as char as i32)   ;;
+  if x{x+=1;}else if y {x=x<<2|1;} else{ return 0; }
+
+
+  {}
+  return s ;
+}"#),
+            r#"let mut x: i32 = 1 + 2 * 3;
+let y: char = 'a';
+
+fn add(a: i32, mut b: i32) -> i32 {
+    return a + b;
+}
+
+#[allow(unused, warnings)]
+fn main() -> i32 {
+    #[deny(unused)]
+    let s: i32 = add(1, x as char as i32);
+    if x {
+        x += 1;
+    } else if y {
+        x = x << 2 | 1;
+    } else {
+        return 0;
+    }
+
+    {}
+    return s;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(
+            fmt("let s: i32 = \"日本\\u{3042}\\\"\\\\\n\"; // コメント\nlet c: char = '\\'';"),
+            "let s: i32 = \"日本あ\\\"\\\\\\n\"; // コメント\nlet c: char = '\\'';\n"
+        );
+    }
+
+    #[test]
+    fn test_parentheses() {
+        assert_eq!(
+            fmt("let x: i32 = ((1 + 2)) * (3 - (4 - 5)) - (6 + 7) + f(8)(9) as char;"),
+            "let x: i32 = (1 + 2) * (3 - (4 - 5)) - (6 + 7) + f(8)(9) as char;\n"
+        );
+        assert_eq!(
+            fmt("let x: i32 = (a || b) && (c == (d < e)) | (f as i32 as char);"),
+            "let x: i32 = (a || b) && (c == (d < e)) | f as i32 as char;\n"
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        assert_eq!(
+            fmt(r#"// header
+
+/* about x */ let x: i32 = 1; // one
+fn main() -> i32 { // opening
+        // leading
+    return /* inside */ x + 2;
+
+    /* before the end */
+}
+// footer"#),
+            r#"// header
+
+/* about x */
+let x: i32 = 1; // one
+
+fn main() -> i32 {
+    // opening
+    // leading
+    return /* inside */ x + 2;
+
+    /* before the end */
+}
+// footer
+"#
+        );
+    }
+
+    #[test]
+    fn test_block_comments() {
+        // Comments stay in the innermost block containing them.
+        assert_eq!(
+            fmt(r#"fn f(x: i32) -> i32 {
+    if x { x = 2; } else { // in else
+        x = 3; /* after three */ }
+    if x == 1 { { // nested
+            x += /* one */ x; // inner
+            // end of nested
+        } } else if x == 2 { x = 4; } else {
+        // only a comment
+    }
+    return f(/* a */ x); // result
+}"#),
+            r#"fn f(x: i32) -> i32 {
+    if x {
+        x = 2;
+    } else {
+        // in else
+        x = 3; /* after three */
+    }
+    if x == 1 {
+        {
+            // nested
+            x += /* one */ x; // inner
+            // end of nested
+        }
+    } else if x == 2 {
+        x = 4;
+    } else {
+        // only a comment
+    }
+    return f(/* a */ x); // result
+}
+"#
+        );
+    }
+
+    /// Examples using syntax which is not implemented yet, e.g. `import`, `type`, `struct` and
+    /// the `i8`/`u64` type names, so that they cannot be formatted
+    const UNPARSABLE: &[&str] = &[
+        "dupdef.mat",
+        "expr.mat",
+        "import.mat",
+        "not_callable.mat",
+        "not_constant.mat",
+        "rec_type.mat",
+        "redef_type.mat",
+        "unres_type.mat",
+        "unresolved_ref.mat",
+    ];
+
+    #[test]
+    fn test_idempotent() {
+        let mut formatted_files = 0;
+        for entry in read_dir("examples").unwrap() {
+            let path = entry.unwrap().path();
+            let code = read_to_string(&path).unwrap();
+            let name = path.file_name().unwrap().to_str().unwrap();
+            let parsed = matc_parser::parse(&path, &code);
+            if UNPARSABLE.contains(&name) {
+                assert!(parsed.is_err(), "{:?} parses now; format it", path);
+                continue;
+            }
+            let ast = parsed.unwrap_or_else(|errors| panic!("{:?}: {:?}", path, errors));
+            let formatted = format(&ast, &code);
+            assert_eq!(formatted, code, "{:?} is not formatted", path);
+            assert_eq!(fmt(&formatted), formatted, "{:?}", path);
+            formatted_files += 1;
+        }
+        assert!(formatted_files > 0);
+    }
+}
@@ -1,8 +1,12 @@
-use crate::sema::entity::Entity;
+use crate::builtin;
+use crate::sema::entity::{Entity, EntityKind};
 use crate::sema::scope::Scope;
-use matc_ast::{Ast, Expr, Stmt};
-use matc_span::{Span, Spanned};
+use matc_ast::visit::{walk_expr, walk_stmt, Visitor, VisitorMut};
+use matc_ast::{Ast, Expr, IdAssigner, Param, Stmt, Type};
+use matc_span::{NodeId, Span, Spanned};
 use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write;
 use std::ops::Deref;
 use std::rc::Rc;
 
@@ -10,14 +14,31 @@ use std::rc::Rc;
 pub(crate) struct Hir {
     pub(crate) defs: Vec<Spanned<Stmt>>,
     pub(crate) scope: Option<Rc<RefCell<Scope>>>,
+    /// Scopes of functions holding their parameters and of blocks, keyed by the IDs of the
+    /// `Stmt::DefFn`s and the `Stmt::Block`s
+    pub(crate) scopes: BTreeMap<NodeId, Rc<RefCell<Scope>>>,
+    /// Variables and functions at use sites, keyed by the IDs of the `Expr::Variable`s
+    pub(crate) resolutions: BTreeMap<NodeId, Res>,
+    /// Every definition including parameters and locals, keyed by its definition ID
+    pub(crate) entities: BTreeMap<NodeId, Entity>,
+}
+
+/// What a name at a use site refers to
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Res {
+    /// A global, function, parameter or local by its definition ID, i.e. the node ID of its name
+    Def(NodeId),
+    Builtin,
 }
 
 impl From<Ast> for Hir {
     fn from(ast: Ast) -> Self {
-        Self {
-            defs: ast.defs,
-            scope: None,
+        let mut defs = ast.defs;
+        let mut ids = IdAssigner::new();
+        for def in &mut defs {
+            ids.visit_stmt_mut(def);
         }
+        Self::from_defs(defs)
     }
 }
 
@@ -40,17 +61,22 @@ impl Hir {
         entities
     }
 
-    pub(crate) fn defined_variables(&self) -> Vec<DefinedVariable> {
+    pub(crate) fn defined_variables(&self) -> Vec<DefinedVariable<'_>> {
         let mut defvars = Vec::<DefinedVariable>::new();
 
         for stmt in &self.defs {
             if let Stmt::DefVar {
-                is_mut, name, expr, ..
+                is_mut,
+                name,
+                ty,
+                expr,
+                ..
             } = stmt.deref()
             {
                 defvars.push(DefinedVariable {
                     is_mut: *is_mut,
                     name,
+                    ty,
                     expr,
                 });
             }
@@ -59,21 +85,178 @@ impl Hir {
         defvars
     }
 
-    pub(crate) fn defined_functions(&self) -> Vec<DefinedFunction> {
+    pub(crate) fn defined_functions(&self) -> Vec<DefinedFunction<'_>> {
         let mut functions = Vec::<DefinedFunction>::new();
 
         for stmt in &self.defs {
-            if let Stmt::DefFn { body, .. } = stmt.deref() {
-                functions.push(DefinedFunction { body })
+            if let Stmt::DefFn {
+                name,
+                args,
+                ret_ty,
+                body,
+                ..
+            } = stmt.deref()
+            {
+                functions.push(DefinedFunction {
+                    name,
+                    args,
+                    ret_ty,
+                    body,
+                })
             }
         }
 
         functions
     }
 
-    #[cfg(test)]
+    /// Use sites of definitions with their spans
+    pub(crate) fn references(&self) -> Vec<(Span, &Entity)> {
+        struct Collector<'a> {
+            hir: &'a Hir,
+            references: Vec<(Span, &'a Entity)>,
+        }
+
+        impl<'a> Visitor<'a> for Collector<'a> {
+            fn visit_expr(&mut self, expr: &'a Spanned<Expr>) {
+                if let Some(Res::Def(id)) = self.hir.resolutions.get(&expr.id) {
+                    if let Some(entity) = self.hir.entities.get(id) {
+                        self.references.push((expr.span, entity));
+                    }
+                }
+                walk_expr(self, expr);
+            }
+        }
+
+        let mut collector = Collector {
+            hir: self,
+            references: Vec::new(),
+        };
+        for def in &self.defs {
+            collector.visit_stmt(def);
+        }
+        collector.references
+    }
+
+    /// The type of `expr` as far as it is known from the resolutions.
+    ///
+    /// Integer operations have the wider type of the operands. Builtins are not values, so only
+    /// calls to them have types.
+    pub(crate) fn type_of(&self, expr: &Spanned<Expr>) -> Option<Type> {
+        match expr.deref() {
+            Expr::Or(..)
+            | Expr::And(..)
+            | Expr::Lt(..)
+            | Expr::Gt(..)
+            | Expr::Lte(..)
+            | Expr::Gte(..)
+            | Expr::Eq(..)
+            | Expr::Neq(..) => Some(Type::I32),
+            Expr::BitOr(lhs, rhs)
+            | Expr::BitXor(lhs, rhs)
+            | Expr::BitAnd(lhs, rhs)
+            | Expr::Shl(lhs, rhs)
+            | Expr::Shr(lhs, rhs)
+            | Expr::Add(lhs, rhs)
+            | Expr::Sub(lhs, rhs)
+            | Expr::Mul(lhs, rhs)
+            | Expr::Div(lhs, rhs)
+            | Expr::Rem(lhs, rhs) => match (self.type_of(lhs)?, self.type_of(rhs)?) {
+                (Type::I8, Type::I8) => Some(Type::I8),
+                _ => Some(Type::I32),
+            },
+            Expr::As(_, ty) => Some(ty.value().clone()),
+            Expr::FnCall { name, .. } => match (name.deref(), self.resolutions.get(&name.id)) {
+                (Expr::Variable(name), Some(Res::Builtin)) => {
+                    builtin::lookup(name).map(|builtin| builtin.ret_ty.clone())
+                }
+                _ => match self.type_of(name)? {
+                    Type::Fn(fn_ty) => Some(*fn_ty.ret),
+                    _ => None,
+                },
+            },
+            Expr::I8(_) => Some(Type::I8),
+            Expr::I32(_) => Some(Type::I32),
+            Expr::Variable(_) => match self.resolutions.get(&expr.id)? {
+                Res::Def(id) => Some(self.entities.get(id)?.ty.value().clone()),
+                Res::Builtin => None,
+            },
+            Expr::String(_) | Expr::Error => None,
+        }
+    }
+
+    /// Whether `expr` names a function, which is a constant unlike other variables
+    pub(crate) fn is_function(&self, expr: &Spanned<Expr>) -> bool {
+        match self.resolutions.get(&expr.id) {
+            Some(Res::Def(id)) => self
+                .entities
+                .get(id)
+                .is_some_and(|entity| entity.kind == EntityKind::Function),
+            _ => false,
+        }
+    }
+
+    /// Prints the tree of scopes with the entities defined in each, for `--dump-scopes`.
+    pub(crate) fn dump_scopes(&self) -> String {
+        struct Dumper<'a> {
+            hir: &'a Hir,
+            depth: usize,
+            out: String,
+        }
+
+        impl Dumper<'_> {
+            fn scope(&mut self, header: String, scope: &Rc<RefCell<Scope>>) {
+                let indent = "    ".repeat(self.depth);
+                writeln!(self.out, "{}{}", indent, header).unwrap();
+                for entity in scope.borrow().entities() {
+                    writeln!(
+                        self.out,
+                        "{}    {} {}: {} @ {:?}",
+                        indent, entity.kind, entity.name.value, entity.ty.value, entity.name.span
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        impl<'a> Visitor<'a> for Dumper<'a> {
+            fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+                let Some(scope) = self.hir.scopes.get(&stmt.id) else {
+                    return walk_stmt(self, stmt);
+                };
+                let header = match stmt.value() {
+                    Stmt::DefFn { name, .. } => format!("fn {}", name.value),
+                    _ => "block".to_string(),
+                };
+                self.depth += 1;
+                self.scope(format!("{} @ {:?}", header, stmt.span), scope);
+                walk_stmt(self, stmt);
+                self.depth -= 1;
+            }
+        }
+
+        let mut dumper = Dumper {
+            hir: self,
+            depth: 0,
+            out: String::new(),
+        };
+        if let Some(toplevel) = &self.scope {
+            dumper.scope("toplevel".to_string(), toplevel);
+        }
+        for def in &self.defs {
+            dumper.visit_stmt(def);
+        }
+        dumper.out
+    }
+
+    /// Makes a HIR of `defs` as they are, whose nodes should have been numbered.
     pub(crate) fn from_defs(defs: Vec<Spanned<Stmt>>) -> Self {
-        Self { defs, scope: None }
+        Self {
+            defs,
+            scope: None,
+            scopes: BTreeMap::new(),
+            resolutions: BTreeMap::new(),
+            entities: BTreeMap::new(),
+        }
     }
 }
 
@@ -81,15 +264,17 @@ impl Hir {
 pub(crate) struct DefinedVariable<'a> {
     pub(crate) is_mut: bool,
     pub(crate) name: &'a Spanned<String>,
+    pub(crate) ty: &'a Spanned<Type>,
     pub(crate) expr: &'a Option<Spanned<Expr>>,
 }
 
 impl<'a> DefinedVariable<'a> {
-    pub(crate) fn is_constant(&self) -> Result<(), Span> {
+    pub(crate) fn is_constant(&self, hir: &Hir) -> Result<(), Span> {
         if self.is_mut {
             return Err(self.name.span);
         } else if let Some(expr) = self.expr {
-            if !expr.value.is_constant() {
+            // Syntax errors are reported by the parser.
+            if !expr.value.is_constant() && !hir.is_function(expr) && *expr.value != Expr::Error {
                 return Err(expr.span);
             }
         }
@@ -97,7 +282,10 @@ impl<'a> DefinedVariable<'a> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub(crate) struct DefinedFunction<'a> {
+    pub(crate) name: &'a Spanned<String>,
+    pub(crate) args: &'a Vec<Param>,
+    pub(crate) ret_ty: &'a Spanned<Type>,
     pub(crate) body: &'a Spanned<Stmt>,
 }
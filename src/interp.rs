@@ -0,0 +1,550 @@
+mod fault;
+mod value;
+
+use crate::builtin;
+use crate::hir::{DefinedFunction, Hir, Res};
+pub(crate) use fault::Fault;
+use matc_ast::{Expr, Stmt, Type};
+use matc_span::{NodeId, Span, Spanned};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::ops::Deref;
+pub(crate) use value::Value;
+
+pub(crate) const MAX_CALL_DEPTH: usize = 10_000;
+
+// Grow the native stack on demand so that deep recursion reaches `MAX_CALL_DEPTH`.
+const RED_ZONE: usize = 128 * 1024;
+const STACK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Runs `main` and returns its value as an exit status.
+pub(crate) fn run(
+    hir: &Hir,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> Result<i32, Fault> {
+    let mut interp = Interpreter::new(hir, Globals::new(), input, output);
+    let status = interp.run_main();
+    output_flush(interp.output);
+    status
+}
+
+/// Evaluates `expr` with the globals of `hir` for the interactive mode.
+///
+/// `globals` persists across inputs; only the globals defined since the last call are
+/// initialized.
+pub(crate) fn eval(
+    hir: &Hir,
+    globals: &mut Globals,
+    expr: &Spanned<Expr>,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> Result<Value, Fault> {
+    let mut interp = Interpreter::new(hir, std::mem::take(globals), input, output);
+    let value = interp.init_globals().and_then(|_| interp.eval(expr));
+    output_flush(interp.output);
+    *globals = interp.globals;
+    value
+}
+
+fn output_flush(output: &mut dyn Write) {
+    // Output errors are not program faults; ignore them as `putchar` does.
+    let _ = output.flush();
+}
+
+enum Flow {
+    Normal,
+    Return(Value),
+}
+
+/// Parameters and locals of a call by their definition IDs, which are unique even when shadowed
+type Frame = HashMap<NodeId, Value>;
+
+/// Values of global variables by their definition IDs
+pub(crate) type Globals = HashMap<NodeId, Value>;
+
+struct Interpreter<'a> {
+    functions: HashMap<NodeId, DefinedFunction<'a>>,
+    globals: Globals,
+    frames: Vec<Frame>,
+    hir: &'a Hir,
+    input: &'a mut dyn BufRead,
+    output: &'a mut dyn Write,
+}
+
+impl<'a> Interpreter<'a> {
+    fn new(
+        hir: &'a Hir,
+        globals: Globals,
+        input: &'a mut dyn BufRead,
+        output: &'a mut dyn Write,
+    ) -> Self {
+        Self {
+            functions: hir
+                .defined_functions()
+                .into_iter()
+                .map(|fun| (fun.name.id, fun))
+                .collect(),
+            globals,
+            frames: Vec::new(),
+            hir,
+            input,
+            output,
+        }
+    }
+
+    fn run_main(&mut self) -> Result<i32, Fault> {
+        self.init_globals()?;
+
+        let main = *self
+            .functions
+            .values()
+            .find(|fun| *fun.name.value == "main")
+            .expect("must not happen: `main` should be checked before running");
+        let status = self.call(main, vec![], main.name.span)?;
+        Ok(status.as_i32().unwrap_or_default())
+    }
+
+    /// Initializes the globals which have no values yet.
+    fn init_globals(&mut self) -> Result<(), Fault> {
+        for var in self.hir.defined_variables() {
+            if self.globals.contains_key(&var.name.id) {
+                continue;
+            }
+            let value = match var.expr {
+                Some(expr) => self.eval(expr)?.cast(var.ty),
+                None => Value::zero(var.ty),
+            };
+            self.globals.insert(var.name.id, value);
+        }
+        Ok(())
+    }
+
+    fn call(
+        &mut self,
+        fun: DefinedFunction<'a>,
+        args: Vec<Value>,
+        span: Span,
+    ) -> Result<Value, Fault> {
+        if fun.args.len() != args.len() {
+            return Err(Fault::ArgCountMismatch(span, fun.args.len(), args.len()));
+        }
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(Fault::StackOverflow(span));
+        }
+
+        let params = fun
+            .args
+            .iter()
+            .zip(args)
+            .map(|(param, arg)| (param.name.id, arg.cast(&param.ty)))
+            .collect();
+        self.frames.push(params);
+        let flow = stacker::maybe_grow(RED_ZONE, STACK_SIZE, || self.exec(fun.body));
+        self.frames.pop();
+
+        Ok(match flow? {
+            Flow::Normal => Value::Void,
+            Flow::Return(value) => value,
+        }
+        .cast(fun.ret_ty))
+    }
+
+    fn call_builtin(&mut self, name: &str, args: Vec<Value>, span: Span) -> Result<Value, Fault> {
+        let builtin = builtin::lookup(name).expect("must not happen: unresolved function");
+        if builtin.arity != args.len() {
+            return Err(Fault::ArgCountMismatch(span, builtin.arity, args.len()));
+        }
+        let int_arg = |i: usize, args: &[Value]| {
+            args[i]
+                .as_i32()
+                .ok_or(Fault::TypeMismatch(span, "an integer"))
+        };
+
+        Ok(match name {
+            "putchar" => {
+                let c = int_arg(0, &args)?;
+                let _ = self.output.write_all(&[c as u8]);
+                Value::I32(c)
+            }
+            "getchar" => {
+                output_flush(self.output);
+                let mut buf = [0];
+                match self.input.read(&mut buf) {
+                    Ok(1) => Value::I32(buf[0] as i32),
+                    _ => Value::I32(-1),
+                }
+            }
+            "puts" => match &args[0] {
+                Value::String(s) => {
+                    let _ = writeln!(self.output, "{}", s);
+                    Value::I32(0)
+                }
+                _ => return Err(Fault::TypeMismatch(span, "a string")),
+            },
+            "print_int" => {
+                let _ = write!(self.output, "{}", int_arg(0, &args)?);
+                Value::Void
+            }
+            _ => unreachable!("must not happen: unknown builtin `{}`", name),
+        })
+    }
+
+    fn exec(&mut self, stmt: &Spanned<Stmt>) -> Result<Flow, Fault> {
+        match stmt.deref() {
+            Stmt::Block(stmts) => return self.exec_block(stmts),
+            Stmt::DefVar { name, ty, expr, .. } => {
+                let value = match expr {
+                    Some(expr) => self.eval(expr)?.cast(ty),
+                    None => Value::zero(ty),
+                };
+                self.frame().insert(name.id, value);
+            }
+            Stmt::If { cond, then, els } => {
+                if self.eval_cond(cond)? {
+                    return self.exec(then);
+                } else if let Some(els) = els {
+                    return self.exec(els);
+                }
+            }
+            Stmt::Return(expr) => {
+                let value = match expr {
+                    Some(expr) => self.eval(expr)?,
+                    None => Value::Void,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Stmt::Assign(lhs, rhs) => {
+                let value = self.eval(rhs)?;
+                self.assign(lhs, value)?;
+            }
+            Stmt::AddAssign(lhs, rhs) => self.assign_op(lhs, rhs, i32::wrapping_add)?,
+            Stmt::SubAssign(lhs, rhs) => self.assign_op(lhs, rhs, i32::wrapping_sub)?,
+            Stmt::MulAssign(lhs, rhs) => self.assign_op(lhs, rhs, i32::wrapping_mul)?,
+            Stmt::DivAssign(lhs, rhs) => {
+                let value = self.div(lhs, rhs, stmt.span, i32::wrapping_div)?;
+                self.assign(lhs, value)?;
+            }
+            Stmt::RemAssign(lhs, rhs) => {
+                let value = self.div(lhs, rhs, stmt.span, i32::wrapping_rem)?;
+                self.assign(lhs, value)?;
+            }
+            Stmt::BitAndAssign(lhs, rhs) => self.assign_op(lhs, rhs, |a, b| a & b)?,
+            Stmt::BitOrAssign(lhs, rhs) => self.assign_op(lhs, rhs, |a, b| a | b)?,
+            Stmt::BitXorAssign(lhs, rhs) => self.assign_op(lhs, rhs, |a, b| a ^ b)?,
+            Stmt::ShlAssign(lhs, rhs) => self.assign_op(lhs, rhs, shl)?,
+            Stmt::ShrAssign(lhs, rhs) => self.assign_op(lhs, rhs, shr)?,
+            Stmt::Expr(expr) => {
+                self.eval(expr)?;
+            }
+            Stmt::Empty | Stmt::DefFn { .. } => {}
+            Stmt::Error => unreachable!("must not happen: code with syntax errors is not run"),
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn exec_block(&mut self, stmts: &[Spanned<Stmt>]) -> Result<Flow, Fault> {
+        for stmt in stmts {
+            if let Flow::Return(value) = self.exec(stmt)? {
+                return Ok(Flow::Return(value));
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn assign_op(
+        &mut self,
+        lhs: &Spanned<Expr>,
+        rhs: &Spanned<Expr>,
+        op: fn(i32, i32) -> i32,
+    ) -> Result<(), Fault> {
+        let value = self.binary(lhs, rhs, op)?;
+        self.assign(lhs, value)
+    }
+
+    fn assign(&mut self, lhs: &Spanned<Expr>, value: Value) -> Result<(), Fault> {
+        let Some(Res::Def(id)) = self.res(lhs) else {
+            return Err(Fault::NotAssignable(lhs.span));
+        };
+        let var = self
+            .frames
+            .last_mut()
+            .and_then(|frame| frame.get_mut(&id))
+            .or_else(|| self.globals.get_mut(&id))
+            .ok_or(Fault::NotAssignable(lhs.span))?;
+        // Keep the declared type of the variable.
+        *var = match var {
+            Value::I8(_) => value.cast(&Type::I8),
+            Value::I32(_) => value.cast(&Type::I32),
+            _ => value,
+        };
+        Ok(())
+    }
+
+    fn eval_cond(&mut self, expr: &Spanned<Expr>) -> Result<bool, Fault> {
+        self.eval(expr)?
+            .is_true()
+            .ok_or(Fault::TypeMismatch(expr.span, "an integer"))
+    }
+
+    fn eval(&mut self, expr: &Spanned<Expr>) -> Result<Value, Fault> {
+        Ok(match expr.deref() {
+            Expr::Or(lhs, rhs) => (self.eval_cond(lhs)? || self.eval_cond(rhs)?).into(),
+            Expr::And(lhs, rhs) => (self.eval_cond(lhs)? && self.eval_cond(rhs)?).into(),
+            Expr::Lt(lhs, rhs) => self.compare(lhs, rhs, |a, b| a < b)?,
+            Expr::Gt(lhs, rhs) => self.compare(lhs, rhs, |a, b| a > b)?,
+            Expr::Lte(lhs, rhs) => self.compare(lhs, rhs, |a, b| a <= b)?,
+            Expr::Gte(lhs, rhs) => self.compare(lhs, rhs, |a, b| a >= b)?,
+            Expr::Eq(lhs, rhs) => self.compare(lhs, rhs, |a, b| a == b)?,
+            Expr::Neq(lhs, rhs) => self.compare(lhs, rhs, |a, b| a != b)?,
+            Expr::BitOr(lhs, rhs) => self.binary(lhs, rhs, |a, b| a | b)?,
+            Expr::BitXor(lhs, rhs) => self.binary(lhs, rhs, |a, b| a ^ b)?,
+            Expr::BitAnd(lhs, rhs) => self.binary(lhs, rhs, |a, b| a & b)?,
+            Expr::Shl(lhs, rhs) => self.binary(lhs, rhs, shl)?,
+            Expr::Shr(lhs, rhs) => self.binary(lhs, rhs, shr)?,
+            Expr::Add(lhs, rhs) => self.binary(lhs, rhs, i32::wrapping_add)?,
+            Expr::Sub(lhs, rhs) => self.binary(lhs, rhs, i32::wrapping_sub)?,
+            Expr::Mul(lhs, rhs) => self.binary(lhs, rhs, i32::wrapping_mul)?,
+            Expr::Div(lhs, rhs) => self.div(lhs, rhs, expr.span, i32::wrapping_div)?,
+            Expr::Rem(lhs, rhs) => self.div(lhs, rhs, expr.span, i32::wrapping_rem)?,
+            Expr::As(expr, ty) => self.eval(expr)?.cast(ty),
+            Expr::FnCall { name, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if let (Expr::Variable(callee), Some(Res::Builtin)) = (name.deref(), self.res(name))
+                {
+                    return self.call_builtin(callee, args, expr.span);
+                }
+                // Calls through variables holding functions are indirect.
+                match self.eval(name)? {
+                    Value::Fn { id, .. } => match self.functions.get(&id) {
+                        Some(&fun) => self.call(fun, args, expr.span)?,
+                        None => unreachable!("must not happen: function values are defined"),
+                    },
+                    _ => return Err(Fault::TypeMismatch(name.span, "a function")),
+                }
+            }
+            Expr::I8(v) => Value::I8(*v),
+            Expr::I32(v) => Value::I32(*v),
+            Expr::String(s) => Value::String(s.as_str().into()),
+            Expr::Variable(_) => {
+                let Some(Res::Def(id)) = self.res(expr) else {
+                    unreachable!("must not happen: variable should be resolved by sema")
+                };
+                if let Some(fun) = self.functions.get(&id) {
+                    return Ok(Value::Fn {
+                        id,
+                        name: fun.name.as_str().into(),
+                    });
+                }
+                self.frames
+                    .last()
+                    .and_then(|frame| frame.get(&id))
+                    .or_else(|| self.globals.get(&id))
+                    .cloned()
+                    .expect("must not happen: variable should be defined before use")
+            }
+            Expr::Error => unreachable!("must not happen: code with syntax errors is not run"),
+        })
+    }
+
+    fn binary(
+        &mut self,
+        lhs: &Spanned<Expr>,
+        rhs: &Spanned<Expr>,
+        op: impl FnOnce(i32, i32) -> i32,
+    ) -> Result<Value, Fault> {
+        let lhs_value = self.eval(lhs)?;
+        let rhs_value = self.eval(rhs)?;
+        if lhs_value.as_i32().is_none() {
+            return Err(Fault::TypeMismatch(lhs.span, "an integer"));
+        }
+        rhs_value
+            .as_i32()
+            .and_then(|_| lhs_value.binary(&rhs_value, op))
+            .ok_or(Fault::TypeMismatch(rhs.span, "an integer"))
+    }
+
+    fn compare(
+        &mut self,
+        lhs: &Spanned<Expr>,
+        rhs: &Spanned<Expr>,
+        op: fn(i32, i32) -> bool,
+    ) -> Result<Value, Fault> {
+        let value = self.binary(lhs, rhs, |a, b| op(a, b) as i32)?;
+        Ok(Value::I32(value.as_i32().unwrap_or_default()))
+    }
+
+    fn div(
+        &mut self,
+        lhs: &Spanned<Expr>,
+        rhs: &Spanned<Expr>,
+        span: Span,
+        op: fn(i32, i32) -> i32,
+    ) -> Result<Value, Fault> {
+        let mut by_zero = false;
+        let value = self.binary(lhs, rhs, |a, b| {
+            if b == 0 {
+                by_zero = true;
+                0
+            } else {
+                op(a, b)
+            }
+        })?;
+        if by_zero {
+            Err(Fault::DivisionByZero(span))
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// What the `Expr::Variable` refers to, resolved by sema
+    fn res(&self, expr: &Spanned<Expr>) -> Option<Res> {
+        self.hir.resolutions.get(&expr.id).copied()
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("must not happen: no frame")
+    }
+}
+
+fn shl(a: i32, b: i32) -> i32 {
+    a.wrapping_shl(b as u32)
+}
+fn shr(a: i32, b: i32) -> i32 {
+    a.wrapping_shr(b as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diag::lint::LintLevels;
+    use crate::diag::Emit;
+    use crate::sema;
+
+    fn run_code(code: &str) -> (Result<i32, Fault>, String) {
+        let ast = matc_parser::parse("test.mat", code).unwrap();
+        let (hir, diagnostics) = sema::analyze(ast, &mut LintLevels::new());
+        assert_eq!(diagnostics.count(), 0, "semantic analysis failed");
+        let mut output = Vec::new();
+        let status = run(&hir, &mut "".as_bytes(), &mut output);
+        (status, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_run() {
+        assert_eq!(
+            run_code(
+                r#"
+let base: i32 = 10;
+
+fn fib(n: i32) -> i32 {
+    if n < 2 {
+        return n;
+    }
+    return fib(n - 1) + fib(n - 2);
+}
+
+fn main() -> i32 {
+    print_int(fib(base));
+    puts("");
+    return 3;
+}
+"#
+            ),
+            (Ok(3), "55\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_wrapping() {
+        assert_eq!(
+            run_code(
+                r#"
+fn main() -> i32 {
+    let c: char = 'a';
+    c += 200;
+    let i: i32 = 2147483647;
+    i += 1;
+    print_int(c);
+    print_int(i);
+    print_int(300 as char);
+    return 0;
+}
+"#
+            ),
+            (Ok(0), "41-214748364844".to_string())
+        );
+    }
+
+    #[test]
+    fn test_shadowing() {
+        assert_eq!(
+            run_code(
+                r#"
+let x: i32 = 1;
+
+fn f(x: i32) -> i32 {
+    {
+        let x: i32 = x + 10;
+        x += 100;
+        print_int(x);
+    }
+    return x;
+}
+
+fn main() -> i32 {
+    print_int(f(2));
+    return x;
+}
+"#
+            ),
+            (Ok(1), "1122".to_string())
+        );
+    }
+
+    #[test]
+    fn test_indirect_call() {
+        assert_eq!(
+            run_code(
+                r#"
+fn inc(x: i32) -> i32 {
+    return x + 1;
+}
+
+fn twice(f: fn(i32) -> i32, x: i32) -> i32 {
+    return f(f(x));
+}
+
+let g: fn(i32) -> i32 = inc;
+
+fn main() -> i32 {
+    let h: fn(fn(i32) -> i32, i32) -> i32 = twice;
+    print_int(h(g, 1));
+    return 0;
+}
+"#
+            ),
+            (Ok(0), "3".to_string())
+        );
+        // An uninitialized function variable holds no function.
+        assert!(matches!(
+            run_code("fn main() -> i32 { let f: fn() -> i32; return f(); }").0,
+            Err(Fault::TypeMismatch(span, "a function")) if span.range() == (46..47)
+        ));
+    }
+
+    #[test]
+    fn test_fault() {
+        assert!(matches!(
+            run_code("fn main() -> i32 { let z: i32 = 0; return 1 / z; }").0,
+            Err(Fault::DivisionByZero(span)) if span.range() == (42..47)
+        ));
+        assert!(matches!(
+            run_code("fn f() -> i32 { return f(); } fn main() -> i32 { return f(); }").0,
+            Err(Fault::StackOverflow(span)) if span.range() == (23..26)
+        ));
+    }
+}
@@ -0,0 +1,53 @@
+use crate::diag::{Diagnostic, Emit, Label};
+use ariadne::Color;
+use matc_span::Span;
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Fault {
+    DivisionByZero(Span),
+    StackOverflow(Span),
+    /// expected, found
+    ArgCountMismatch(Span, usize, usize),
+    /// expected
+    TypeMismatch(Span, &'static str),
+    NotAssignable(Span),
+}
+
+impl Emit for Fault {
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let (span, message, label) = match *self {
+            Fault::DivisionByZero(span) => (
+                span,
+                "Division by zero",
+                "attempted to divide by zero".to_string(),
+            ),
+            Fault::StackOverflow(span) => (
+                span,
+                "Stack overflow",
+                "call stack exhausted while calling this".to_string(),
+            ),
+            Fault::ArgCountMismatch(span, expected, found) => (
+                span,
+                "Argument count mismatch",
+                format!("expected {} arguments, found {}", expected, found),
+            ),
+            Fault::TypeMismatch(span, expected) => {
+                (span, "Type mismatch", format!("expected {}", expected))
+            }
+            Fault::NotAssignable(span) => (
+                span,
+                "Not assignable",
+                "cannot assign to this expression".to_string(),
+            ),
+        };
+        let mut diagnostic = Diagnostic::error(span, message)
+            .with_label(Label::new(span).with_message(label).with_color(Color::Red));
+        if let Fault::StackOverflow(_) = self {
+            diagnostic = diagnostic.with_note(format!(
+                "the call depth is limited to {}",
+                super::MAX_CALL_DEPTH
+            ));
+        }
+        vec![diagnostic]
+    }
+}
@@ -0,0 +1,106 @@
+use matc_ast::Type;
+use matc_span::NodeId;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Value {
+    Void,
+    I8(i8),
+    I32(i32),
+    String(Rc<str>),
+    /// A function by its definition ID
+    Fn {
+        id: NodeId,
+        name: Rc<str>,
+    },
+}
+
+impl Value {
+    pub(crate) fn zero(ty: &Type) -> Self {
+        Self::I32(0).cast(ty)
+    }
+
+    pub(crate) fn as_i32(&self) -> Option<i32> {
+        match *self {
+            Value::I8(v) => Some(v as i32),
+            Value::I32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_true(&self) -> Option<bool> {
+        self.as_i32().map(|v| v != 0)
+    }
+
+    /// Converts into `ty`, wrapping around on truncation like `as` in Rust.
+    pub(crate) fn cast(&self, ty: &Type) -> Self {
+        match (ty, self) {
+            (Type::Void, _) => Value::Void,
+            (Type::I8, _) => Value::I8(self.as_i32().unwrap_or_default() as i8),
+            (Type::I32, _) => Value::I32(self.as_i32().unwrap_or_default()),
+            // Sema allows functions to be converted only into the same type.
+            (Type::Fn(_), _) => self.clone(),
+            (Type::Error, _) => unreachable!("must not happen: code with syntax errors is not run"),
+        }
+    }
+
+    /// Applies a binary integer operation with the usual arithmetic conversion;
+    /// the result has the wider type of both operands.
+    pub(crate) fn binary(&self, rhs: &Self, op: impl FnOnce(i32, i32) -> i32) -> Option<Self> {
+        let result = op(self.as_i32()?, rhs.as_i32()?);
+        match (self, rhs) {
+            (Value::I8(_), Value::I8(_)) => Some(Value::I8(result as i8)),
+            _ => Some(Value::I32(result)),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::I32(b as i32)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Void => write!(f, "()"),
+            Value::I8(v) => write!(f, "{}", v),
+            Value::I32(v) => write!(f, "{}", v),
+            Value::String(s) => write!(f, "{:?}", s),
+            Value::Fn { name, .. } => write!(f, "fn {}", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cast() {
+        assert_eq!(Value::I32(300).cast(&Type::I8), Value::I8(44));
+        assert_eq!(Value::I32(-129).cast(&Type::I8), Value::I8(127));
+        assert_eq!(Value::I8(-1).cast(&Type::I32), Value::I32(-1));
+        assert_eq!(Value::I8(1).cast(&Type::Void), Value::Void);
+    }
+
+    #[test]
+    fn test_binary() {
+        let add = |a: i32, b: i32| a.wrapping_add(b);
+        assert_eq!(
+            Value::I8(127).binary(&Value::I8(1), add),
+            Some(Value::I8(-128))
+        );
+        assert_eq!(
+            Value::I8(127).binary(&Value::I32(1), add),
+            Some(Value::I32(128))
+        );
+        assert_eq!(
+            Value::I32(i32::MAX).binary(&Value::I32(1), add),
+            Some(Value::I32(i32::MIN))
+        );
+        assert_eq!(Value::String("a".into()).binary(&Value::I32(1), add), None);
+    }
+}
@@ -0,0 +1,159 @@
+use matc_ast::visit::{walk_expr, walk_stmt, Visitor};
+use matc_ast::{Ast, Expr, Stmt, Type};
+use matc_span::{Span, Spanned};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::ops::Deref;
+
+/// Encodes `ast` for external tools with the `serde` feature of `matc_ast`, where enums are
+/// tagged with their variant names such as `{"I32": 1}` and every node carries its span as
+/// `{"file": "a.mat", "start": 0, "end": 1}`.
+pub(crate) fn to_json(ast: &Ast) -> Value {
+    serde_json::to_value(ast).expect("an AST always has a JSON representation")
+}
+
+/// Decodes an AST in the format of `to_json`, e.g. generated by an external tool.
+///
+/// The placeholders of syntax errors are rejected, since the errors themselves are lost and the
+/// AST must not be run.
+pub(crate) fn from_json(json: Value) -> Result<Ast, String> {
+    let ast: Ast = serde_json::from_value(json).map_err(|err| err.to_string())?;
+    let mut finder = ErrorFinder(None);
+    for def in &ast.defs {
+        finder.visit_stmt(def);
+    }
+    match finder.0 {
+        Some(span) => Err(format!("a syntax error is left at `{:?}`", span)),
+        None => Ok(ast),
+    }
+}
+
+/// Finds the first `Stmt::Error`, `Expr::Error` or `Type::Error`.
+struct ErrorFinder(Option<Span>);
+
+impl<'a> Visitor<'a> for ErrorFinder {
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        if let Stmt::Error = stmt.deref() {
+            self.0.get_or_insert(stmt.span);
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &'a Spanned<Expr>) {
+        if let Expr::Error = expr.deref() {
+            self.0.get_or_insert(expr.span);
+        }
+        walk_expr(self, expr);
+    }
+
+    fn visit_ty(&mut self, ty: &'a Spanned<Type>) {
+        if ty.has_error() {
+            self.0.get_or_insert(ty.span);
+        }
+    }
+}
+
+/// Files which the spans in `json` point to
+pub(crate) fn files(json: &Value) -> BTreeSet<String> {
+    fn collect(json: &Value, files: &mut BTreeSet<String>) {
+        match json {
+            Value::Array(array) => array.iter().for_each(|json| collect(json, files)),
+            Value::Object(map) => {
+                if let Some(file) = map.get("span").map(|span| &span["file"]) {
+                    files.extend(file.as_str().map(str::to_string));
+                }
+                map.values().for_each(|json| collect(json, files));
+            }
+            _ => {}
+        }
+    }
+
+    let mut files = BTreeSet::new();
+    collect(json, &mut files);
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matc_span::SrcId;
+    use serde_json::json;
+    use std::fs::{read_dir, read_to_string};
+
+    #[test]
+    fn test_span() {
+        let code = "let x: i32 = 1;";
+        let ast = matc_parser::parse("main.mat", code).unwrap();
+        let json = to_json(&ast);
+        assert_eq!(
+            json["defs"][0]["value"]["DefVar"]["name"],
+            json!({
+                "value": "x",
+                "span": {"file": "main.mat", "start": 4, "end": 5},
+            })
+        );
+        assert_eq!(files(&json), BTreeSet::from(["main.mat".to_string()]));
+
+        let ast = matc_parser::parse("/tmp/main.mat", code).unwrap();
+        let json = to_json(&ast);
+        assert_eq!(files(&json), BTreeSet::from(["/tmp/main.mat".to_string()]));
+        let decoded = from_json(json).unwrap();
+        assert_eq!(
+            decoded.defs[0].span.src(),
+            SrcId::from_path("/tmp/main.mat")
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for entry in read_dir("examples").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().filter(|&ext| ext == "mat").is_none() {
+                continue;
+            }
+            let code = read_to_string(&path).unwrap();
+            let (Some(ast), errors) = matc_parser::parse_recovery(&path, &code) else {
+                continue;
+            };
+            let json: Value = serde_json::from_str(&to_json(&ast).to_string()).unwrap();
+            // `from_json` rejects the placeholders of syntax errors, which serde keeps.
+            let decoded: Ast = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(from_json(json.clone()).is_ok(), errors.is_empty());
+            assert_eq!(decoded, ast, "{}", path.display());
+            // `Spanned` ignores spans on comparison.
+            assert_eq!(to_json(&decoded), json, "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let span = json!({"file": "a", "start": 0, "end": 1});
+        let parse = |json| from_json(json).unwrap_err();
+        assert!(parse(json!({"defs": [], "comments": 1})).contains("expected a sequence"));
+        assert!(
+            parse(json!({"defs": [{"value": "Foo", "span": span}], "comments": []}))
+                .contains("unknown variant `Foo`")
+        );
+        let expr = json!({"value": {"I8": 128}, "span": span});
+        assert!(
+            parse(json!({"defs": [{"value": {"Expr": expr}, "span": span}], "comments": []}))
+                .contains("expected i8")
+        );
+        // Placeholders of syntax errors
+        let error = json!({"value": "Error", "span": span});
+        assert_eq!(
+            parse(json!({"defs": [error], "comments": []})),
+            "a syntax error is left at `a:0..1`"
+        );
+        let stmt = json!({"value": {"Expr": error}, "span": span});
+        assert_eq!(
+            parse(json!({"defs": [stmt], "comments": []})),
+            "a syntax error is left at `a:0..1`"
+        );
+        let span = json!({"file": "a", "start": 1, "end": 0});
+        assert!(
+            parse(json!({"defs": [], "comments": [{"value": {"Line": ""}, "span": span}]}))
+                .contains("span ends at 0 before its start 1")
+        );
+    }
+}
@@ -0,0 +1,64 @@
+mod builtin;
+mod cli;
+mod diag;
+mod formatter;
+mod hir;
+mod interp;
+mod json;
+mod lsp;
+mod repl;
+mod sema;
+mod util;
+
+pub use cli::main;
+pub use diag::{Diagnostic, Label, Severity};
+
+use diag::lint::LintLevels;
+use matc_ast::Ast;
+
+/// Runs every semantic check on `ast` with the default lint levels, e.g. on an AST deserialized
+/// with the `serde` feature of `matc_ast`, and returns the diagnostics.
+pub fn check(ast: Ast) -> Vec<Diagnostic> {
+    sema::check(ast, LintLevels::new()).1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check() {
+        let span = |start, end| json!({"file": "gen.mat", "start": start, "end": end});
+        // `let x: i32 = y;` from a generator
+        let ast = json!({
+            "defs": [{
+                "value": {"DefVar": {
+                    "attrs": [],
+                    "is_mut": false,
+                    "name": {"value": "x", "span": span(4, 5)},
+                    "ty": {"value": "I32", "span": span(7, 10)},
+                    "expr": {"value": {"Variable": "y"}, "span": span(13, 14)},
+                }},
+                "span": span(0, 15),
+            }],
+            "comments": [],
+        });
+        let diagnostics = check(serde_json::from_value(ast).unwrap());
+        let codes: Vec<_> = diagnostics.iter().map(|diag| diag.code).collect();
+        assert_eq!(codes, [Some("W0001"), Some("E0102"), Some("E0103")]);
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+        assert_eq!(diagnostics[1].span.range(), 13..14);
+    }
+
+    #[test]
+    fn test_check_unknown_types() {
+        // Nothing but the unknown types, which are syntax errors, is wrong.
+        let code =
+            "fn main(a: foo, g: fn(bar) -> i32) -> baz {\n    let x: baz = a;\n    a = g(x);\n}\n";
+        let (ast, errors) = matc_parser::parse_recovery("main.mat", code);
+        assert_eq!(errors.len(), 4);
+        let diagnostics = check(ast.unwrap());
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+}
@@ -0,0 +1,520 @@
+mod protocol;
+
+use crate::diag::lint::LintLevels;
+use crate::diag::{Diagnostic, Emit, Severity};
+use crate::hir::Hir;
+use crate::sema;
+use crate::sema::entity::{Entity, EntityKind};
+use matc_span::{LineIndex, Span};
+use protocol::{location, range, read_message, write_message};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+
+const SEVERITY_ERROR: i32 = 1;
+const SEVERITY_WARNING: i32 = 2;
+
+/// Serves the Language Server Protocol over `input` and `output` until `exit`.
+pub(crate) fn run(input: &mut dyn BufRead, output: &mut dyn Write) -> anyhow::Result<()> {
+    let mut server = Server::default();
+    while let Some(content) = read_message(input)? {
+        let message = match decode(&content) {
+            Ok(message) => message,
+            Err((id, code, message)) => {
+                write_error(output, &id, code, message)?;
+                continue;
+            }
+        };
+        if !server.handle(&message, output)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a JSON-RPC message, or returns the id, the code and the message of the error to
+/// reply, where the id is null unless it can be read.
+fn decode(content: &[u8]) -> Result<Value, (Value, i32, String)> {
+    let message: Value = serde_json::from_slice(content)
+        .map_err(|err| (Value::Null, PARSE_ERROR, format!("invalid JSON: {}", err)))?;
+    // A request or a notification has a method, and a response has a result or an error.
+    let valid = match message.get("method") {
+        Some(method) => method.is_string(),
+        None => message.get("result").is_some() || message.get("error").is_some(),
+    };
+    if !valid {
+        let id = message.get("id").cloned().unwrap_or_default();
+        return Err((id, INVALID_REQUEST, "not a JSON-RPC message".to_string()));
+    }
+    Ok(message)
+}
+
+fn write_error(
+    output: &mut dyn Write,
+    id: &Value,
+    code: i32,
+    message: String,
+) -> anyhow::Result<()> {
+    write_message(
+        output,
+        &json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": code, "message": message},
+        }),
+    )
+}
+
+struct Document {
+    lines: LineIndex,
+    // Available only when the document has been parsed successfully
+    hir: Option<Hir>,
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<String, Document>,
+}
+
+impl Server {
+    /// Returns `false` when the client asks to exit.
+    fn handle(&mut self, message: &Value, output: &mut dyn Write) -> anyhow::Result<bool> {
+        let Some(method) = message["method"].as_str() else {
+            // Responses to our requests, which we never send
+            return Ok(true);
+        };
+        let id = &message["id"];
+        let params = &message["params"];
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1, // Full
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "hoverProvider": true,
+                },
+            }),
+            "shutdown" => Value::Null,
+            "exit" => return Ok(false),
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                if let (Some(uri), Some(text)) =
+                    (document["uri"].as_str(), document["text"].as_str())
+                {
+                    self.update(uri, text.to_string(), output)?;
+                }
+                return Ok(true);
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str();
+                // We only advertise full synchronization, so the last change has the whole text.
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    self.update(uri, text.to_string(), output)?;
+                }
+                return Ok(true);
+            }
+            "textDocument/didClose" => {
+                if let Some(uri) = params["textDocument"]["uri"].as_str() {
+                    self.documents.remove(uri);
+                    publish_diagnostics(output, uri, vec![])?;
+                }
+                return Ok(true);
+            }
+            "textDocument/definition" => self
+                .entity_at(params)
+                .map(|(uri, lines, entity)| location(uri, lines, entity.name.span))
+                .unwrap_or(Value::Null),
+            "textDocument/references" => self.references(params),
+            "textDocument/hover" => self.hover(params),
+            _ if id.is_null() => return Ok(true), // Ignore unknown notifications
+            _ => {
+                let message = format!("unsupported method `{}`", method);
+                write_error(output, id, METHOD_NOT_FOUND, message)?;
+                return Ok(true);
+            }
+        };
+
+        write_message(
+            output,
+            &json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        )?;
+        Ok(true)
+    }
+
+    /// Analyzes the new text of a document and publishes its diagnostics.
+    fn update(&mut self, uri: &str, text: String, output: &mut dyn Write) -> anyhow::Result<()> {
+        let (ast, errors) = matc_parser::parse_recovery(uri, &text);
+        let lines = LineIndex::new(text);
+        let mut diagnostics = errors.diagnostics();
+        let hir = ast.map(|ast| {
+            let (hir, sema_diagnostics) = sema::check(ast, LintLevels::new());
+            diagnostics.extend(sema_diagnostics);
+            hir
+        });
+        let diagnostics = diagnostics
+            .iter()
+            .map(|diag| diagnostic(uri, &lines, diag))
+            .collect();
+
+        publish_diagnostics(output, uri, diagnostics)?;
+        self.documents
+            .insert(uri.to_string(), Document { lines, hir });
+        Ok(())
+    }
+
+    /// Finds the entity defined or referred to at the position of `params`.
+    fn entity_at<'a>(&'a self, params: &'a Value) -> Option<(&'a str, &'a LineIndex, Entity)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let document = self.documents.get(uri)?;
+        let hir = document.hir.as_ref()?;
+        let offset = protocol::offset(&document.lines, &params["position"])?;
+        // Allow the cursor to be just after the name
+        let contains = |span: Span| span.range().start <= offset && offset <= span.range().end;
+
+        let references = hir.references();
+        let entity = match references.iter().find(|(span, _)| contains(*span)) {
+            Some((_, entity)) => entity,
+            None => hir
+                .entities
+                .values()
+                .find(|entity| contains(entity.name.span))?,
+        };
+        Some((uri, &document.lines, entity.clone()))
+    }
+
+    fn references(&self, params: &Value) -> Value {
+        let Some((uri, lines, entity)) = self.entity_at(params) else {
+            return Value::Null;
+        };
+        let hir = self.documents[uri].hir.as_ref().unwrap();
+
+        let mut spans = Vec::new();
+        if params["context"]["includeDeclaration"]
+            .as_bool()
+            .unwrap_or(false)
+        {
+            spans.push(entity.name.span);
+        }
+        spans.extend(
+            hir.references()
+                .into_iter()
+                .filter(|(_, target)| target.id() == entity.id())
+                .map(|(span, _)| span),
+        );
+        spans
+            .into_iter()
+            .map(|span| location(uri, lines, span))
+            .collect()
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let Some((uri, _, entity)) = self.entity_at(params) else {
+            return Value::Null;
+        };
+        let hir = self.documents[uri].hir.as_ref().unwrap();
+
+        // Show the signatures of functions with the names of their parameters.
+        let function = match entity.kind {
+            EntityKind::Function => hir
+                .defined_functions()
+                .into_iter()
+                .find(|fun| fun.name.id == entity.id()),
+            _ => None,
+        };
+        let signature = match function {
+            Some(fun) => format!(
+                "fn {}({}) -> {}",
+                fun.name.value,
+                fun.args
+                    .iter()
+                    .map(|arg| format!(
+                        "{}{}: {}",
+                        if arg.is_mut { "mut " } else { "" },
+                        arg.name.value,
+                        arg.ty.value
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                fun.ret_ty.value
+            ),
+            None => format!("{}: {}", entity.name.value, entity.ty.value),
+        };
+        json!({
+            "contents": {
+                "kind": "markdown",
+                "value": format!("```mat\n{}\n```", signature),
+            },
+        })
+    }
+}
+
+fn publish_diagnostics(
+    output: &mut dyn Write,
+    uri: &str,
+    diagnostics: Vec<Value>,
+) -> anyhow::Result<()> {
+    write_message(
+        output,
+        &json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {"uri": uri, "diagnostics": diagnostics},
+        }),
+    )
+}
+
+fn diagnostic(uri: &str, lines: &LineIndex, diagnostic: &Diagnostic) -> Value {
+    let severity = match diagnostic.severity {
+        Severity::Error => SEVERITY_ERROR,
+        Severity::Warning => SEVERITY_WARNING,
+    };
+    // LSP has no notes, so they are appended to the message as rustc does.
+    let notes = diagnostic.notes.iter().map(|note| ("note", note));
+    let helps = diagnostic.helps.iter().map(|help| ("help", help));
+    let message = notes
+        .chain(helps)
+        .fold(diagnostic.message.clone(), |message, (kind, note)| {
+            format!("{}\n{}: {}", message, kind, note)
+        });
+    let mut json = json!({
+        "range": range(lines, diagnostic.span),
+        "severity": severity,
+        "source": "matc",
+        "message": message,
+    });
+    let related: Vec<_> = diagnostic
+        .labels
+        .iter()
+        .filter(|label| label.span != diagnostic.span)
+        .filter_map(|label| {
+            Some(json!({
+                "location": location(uri, lines, label.span),
+                "message": label.message.as_ref()?,
+            }))
+        })
+        .collect();
+    if !related.is_empty() {
+        json["relatedInformation"] = related.into();
+    }
+    if let Some(code) = diagnostic.code {
+        json["code"] = code.into();
+    }
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///main.mat";
+
+    /// Sends `messages` to a server and returns the messages it wrote.
+    fn exchange(messages: Vec<Value>) -> Vec<Value> {
+        let mut input = Vec::new();
+        for message in messages {
+            write_message(&mut input, &message).unwrap();
+        }
+        exchange_raw(&input)
+    }
+
+    /// Sends framed `input` to a server and returns the messages it wrote.
+    fn exchange_raw(input: &[u8]) -> Vec<Value> {
+        let mut output = Vec::new();
+        run(&mut &input[..], &mut output).unwrap();
+
+        let mut output = &output[..];
+        let mut responses = Vec::new();
+        while let Some(content) = read_message(&mut output).unwrap() {
+            responses.push(serde_json::from_slice(&content).unwrap());
+        }
+        responses
+    }
+
+    fn request(id: usize, method: &str, params: Value) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    fn notification(method: &str, params: Value) -> Value {
+        json!({"jsonrpc": "2.0", "method": method, "params": params})
+    }
+
+    fn did_open(text: &str) -> Value {
+        notification(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": URI,
+                    "languageId": "mat",
+                    "version": 1,
+                    "text": text,
+                },
+            }),
+        )
+    }
+
+    fn at(line: usize, character: usize) -> Value {
+        json!({
+            "textDocument": {"uri": URI},
+            "position": {"line": line, "character": character},
+            "context": {"includeDeclaration": true},
+        })
+    }
+
+    fn span(start: (usize, usize), end: (usize, usize)) -> Value {
+        let pos = |(line, character)| json!({"line": line, "character": character});
+        json!({"start": pos(start), "end": pos(end)})
+    }
+
+    const CODE: &str = "\
+let gvar: i32 = 1;
+fn add(a: i32, b: i32) -> i32 {
+    return a + b + gvar;
+}
+fn main() -> i32 {
+    /* 😀 */ return add(gvar, 2);
+}
+";
+
+    #[test]
+    fn test_lifecycle() {
+        let responses = exchange(vec![
+            request(1, "initialize", json!({})),
+            notification("initialized", json!({})),
+            request(2, "textDocument/completion", json!({})),
+            request(3, "shutdown", Value::Null),
+            notification("exit", Value::Null),
+            request(4, "shutdown", Value::Null),
+        ]);
+        assert_eq!(responses.len(), 3);
+        assert_eq!(
+            responses[0]["result"]["capabilities"]["definitionProvider"],
+            true
+        );
+        assert_eq!(responses[1]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(responses[2]["result"], Value::Null);
+    }
+
+    #[test]
+    fn test_malformed() {
+        let mut input = Vec::new();
+        for content in ["{\"jsonrpc\": \"2.0\", \"id\": 1,", "[1, 2]", "{\"id\": 2}"] {
+            write!(
+                input,
+                "Content-Length: {}\r\n\r\n{}",
+                content.len(),
+                content
+            )
+            .unwrap();
+        }
+        write_message(&mut input, &request(3, "shutdown", Value::Null)).unwrap();
+
+        let responses = exchange_raw(&input);
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0]["error"]["code"], PARSE_ERROR);
+        assert_eq!(responses[0]["id"], Value::Null);
+        assert_eq!(responses[1]["error"]["code"], INVALID_REQUEST);
+        assert_eq!(responses[2]["error"]["code"], INVALID_REQUEST);
+        assert_eq!(responses[2]["id"], 2);
+        // The server keeps serving after them.
+        assert_eq!(responses[3]["id"], 3);
+        assert_eq!(responses[3]["result"], Value::Null);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let responses = exchange(vec![
+            did_open("let x: i32 = y;\n"),
+            notification(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": {"uri": URI},
+                    "contentChanges": [
+                        {"text": "let x: i32 = 1;\nfn f() -> i32 {\n    return x; // é"},
+                    ],
+                }),
+            ),
+        ]);
+
+        let diagnostics = &responses[0]["params"]["diagnostics"];
+        assert_eq!(
+            diagnostics,
+            &json!([
+                {
+                    "range": span((0, 4), (0, 5)),
+                    "severity": SEVERITY_WARNING,
+                    "source": "matc",
+                    "message": "Unused entity",
+                    "code": "W0001",
+                },
+                {
+                    "range": span((0, 13), (0, 14)),
+                    "severity": SEVERITY_ERROR,
+                    "source": "matc",
+                    "message": "Unresolved reference\nhelp: a toplevel definition with a similar name exists: `x`",
+                    "code": "E0102",
+                },
+                {
+                    "range": span((0, 13), (0, 14)),
+                    "severity": SEVERITY_ERROR,
+                    "source": "matc",
+                    "message": "Not a constant\nnote: toplevel definitions should be constants",
+                    "code": "E0103",
+                },
+            ])
+        );
+
+        let diagnostics = &responses[1]["params"]["diagnostics"];
+        assert_eq!(diagnostics.as_array().unwrap().len(), 2);
+        // The end of input is out of the text
+        assert_eq!(diagnostics[0]["range"], span((2, 18), (2, 18)));
+        // The unclosed function is still analyzed.
+        assert_eq!(diagnostics[1]["code"], "W0001");
+        assert_eq!(diagnostics[1]["range"], span((1, 3), (1, 4)));
+    }
+
+    #[test]
+    fn test_navigation() {
+        let responses = exchange(vec![
+            did_open(CODE),
+            // `gvar` in `add(gvar, 2)`, after a surrogate pair in UTF-16
+            request(1, "textDocument/definition", at(5, 27)),
+            request(2, "textDocument/references", at(0, 5)),
+            // `b` in `return a + b + gvar`
+            request(3, "textDocument/definition", at(2, 15)),
+            request(4, "textDocument/hover", at(5, 21)),
+            request(5, "textDocument/hover", at(0, 4)),
+            request(6, "textDocument/definition", at(3, 0)),
+        ]);
+        assert_eq!(responses[0]["params"]["diagnostics"], json!([]));
+
+        let location = |range| json!({"uri": URI, "range": range});
+        assert_eq!(responses[1]["result"], location(span((0, 4), (0, 8))));
+        assert_eq!(
+            responses[2]["result"],
+            json!([
+                location(span((0, 4), (0, 8))),
+                location(span((2, 19), (2, 23))),
+                location(span((5, 24), (5, 28))),
+            ])
+        );
+        assert_eq!(responses[3]["result"], location(span((1, 15), (1, 16))));
+        assert_eq!(
+            responses[4]["result"]["contents"]["value"],
+            "```mat\nfn add(a: i32, b: i32) -> i32\n```"
+        );
+        assert_eq!(
+            responses[5]["result"]["contents"]["value"],
+            "```mat\ngvar: i32\n```"
+        );
+        assert_eq!(responses[6]["result"], Value::Null);
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::bail;
+use matc_span::{Encoding, LineCol, LineIndex, Span};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+/// Reads the content of a message framed by a `Content-Length` header, or `None` on EOF.
+///
+/// Only broken framing is an error, after which the next message cannot be found; the content
+/// is decoded by the caller to reply to a malformed one.
+pub(super) fn read_message(input: &mut dyn BufRead) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+
+    let Some(length) = length else {
+        bail!("missing `Content-Length` header");
+    };
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+    Ok(Some(content))
+}
+
+pub(super) fn write_message(output: &mut dyn Write, message: &Value) -> anyhow::Result<()> {
+    let content = message.to_string();
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()?;
+    Ok(())
+}
+
+/// Converts a byte offset into a 0-based position, whose character is counted in UTF-16 code
+/// units.
+pub(super) fn position(lines: &LineIndex, offset: usize) -> Value {
+    let LineCol { line, column } = lines.line_col(offset, Encoding::Utf16);
+    json!({"line": line - 1, "character": column - 1})
+}
+
+/// Converts a position into a byte offset; the inverse of `position`.
+pub(super) fn offset(lines: &LineIndex, position: &Value) -> Option<usize> {
+    let number = |key| position[key].as_u64().map(|n| n as usize + 1);
+    let line_col = LineCol {
+        line: number("line")?,
+        column: number("character")?,
+    };
+    lines.offset(line_col, Encoding::Utf16)
+}
+
+pub(super) fn range(lines: &LineIndex, span: Span) -> Value {
+    json!({
+        "start": position(lines, span.range().start),
+        "end": position(lines, span.range().end),
+    })
+}
+
+pub(super) fn location(uri: &str, lines: &LineIndex, span: Span) -> Value {
+    json!({"uri": uri, "range": range(lines, span)})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position() {
+        // `😀` is a surrogate pair in UTF-16.
+        let lines = LineIndex::new("a😀b\n// é\nc");
+        for (offset, line, character) in [(0, 0, 0), (5, 0, 3), (6, 0, 4), (7, 1, 0), (13, 2, 0)] {
+            let pos = position(&lines, offset);
+            assert_eq!(pos, json!({"line": line, "character": character}));
+            assert_eq!(super::offset(&lines, &pos), Some(offset));
+        }
+        assert_eq!(super::offset(&lines, &position(&lines, 14)), Some(14));
+        assert_eq!(
+            super::offset(&lines, &json!({"line": 5, "character": 0})),
+            None
+        );
+    }
+
+    #[test]
+    fn test_message() {
+        let message = json!({"jsonrpc": "2.0", "method": "exit"});
+        let mut buf = Vec::new();
+        write_message(&mut buf, &message).unwrap();
+        assert!(buf.starts_with(b"Content-Length: 33\r\n\r\n"));
+
+        let mut input = &buf[..];
+        assert_eq!(
+            read_message(&mut input).unwrap(),
+            Some(message.to_string().into_bytes())
+        );
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+}
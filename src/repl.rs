@@ -0,0 +1,344 @@
+use crate::diag::lint::LintLevels;
+use crate::diag::{Emit, Emitter, ErrorFormat};
+use crate::hir::Hir;
+use crate::interp::{self, Globals, Value};
+use crate::sema::{self, scope::Scope};
+use matc_ast::visit::VisitorMut;
+use matc_ast::{Expr, IdAssigner, Line};
+use matc_parser::error::SimpleReason;
+use matc_span::{SourceDb, Spanned, SrcId};
+use std::cell::RefCell;
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+
+const SRC: &str = "<repl>";
+const PROMPT: &str = ">> ";
+const PROMPT_CONTINUED: &str = ".. ";
+
+const HELP: &str = "\
+:type <expr>  Show the type of an expression
+:ast <input>  Show the AST of an input
+:reset        Discard all definitions
+:help         Show this message
+";
+
+/// Whether the input needs more lines
+enum Status {
+    Done,
+    Incomplete,
+}
+
+pub(crate) fn run(input: &mut dyn BufRead, output: &mut dyn Write) -> anyhow::Result<()> {
+    let mut repl = Repl::new();
+    let mut buf = String::new();
+    loop {
+        let prompt = if buf.is_empty() {
+            PROMPT
+        } else {
+            PROMPT_CONTINUED
+        };
+        write!(output, "{}", prompt)?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            // EOF
+            writeln!(output)?;
+            return Ok(());
+        }
+        buf.push_str(&line);
+
+        if let Status::Done = repl.feed(&buf, input, output)? {
+            buf.clear();
+        }
+    }
+}
+
+struct Repl {
+    /// Definitions accepted so far with their resolutions
+    hir: Hir,
+    /// Values of the globals, which persist across inputs
+    globals: Globals,
+    toplevel: Rc<RefCell<Scope>>,
+    /// Numbers the nodes of every input, so that they are unique across the session
+    ids: IdAssigner,
+    // Every input so far, which spans of the session point to.
+    code: String,
+}
+
+impl Repl {
+    fn new() -> Self {
+        Self {
+            hir: Hir::from_defs(Vec::new()),
+            globals: Globals::new(),
+            toplevel: Scope::new(None),
+            ids: IdAssigner::new(),
+            code: String::new(),
+        }
+    }
+
+    fn feed(
+        &mut self,
+        input: &str,
+        stdin: &mut dyn BufRead,
+        stdout: &mut dyn Write,
+    ) -> anyhow::Result<Status> {
+        let trimmed = input.trim_start();
+        let status = if let Some(command) = trimmed.strip_prefix(':') {
+            let (name, arg) = command
+                .split_once(char::is_whitespace)
+                .unwrap_or((command, ""));
+            let arg_offset = input.len() - arg.len();
+            match name.trim_end() {
+                "type" => self.type_of(input, arg_offset, stdout)?,
+                "ast" => self.ast(input, arg_offset, stdout)?,
+                "reset" => {
+                    *self = Self::new();
+                    return Ok(Status::Done);
+                }
+                "help" => {
+                    write!(stdout, "{}", HELP)?;
+                    Status::Done
+                }
+                _ => {
+                    writeln!(
+                        stdout,
+                        "Unknown command `:{}`; try `:help`",
+                        name.trim_end()
+                    )?;
+                    Status::Done
+                }
+            }
+        } else if trimmed.is_empty() {
+            Status::Done
+        } else {
+            self.eval(input, stdin, stdout)?
+        };
+
+        if let Status::Done = status {
+            self.code.push_str(input);
+        }
+        Ok(status)
+    }
+
+    fn eval(
+        &mut self,
+        input: &str,
+        stdin: &mut dyn BufRead,
+        stdout: &mut dyn Write,
+    ) -> anyhow::Result<Status> {
+        let line = match self.parse(input, 0, stdout) {
+            Ok(line) => line,
+            Err(status) => return Ok(status),
+        };
+        let db = self.db(input);
+        let emitter = || Emitter::new(ErrorFormat::Human, &db);
+
+        match line {
+            Line::Defs(mut defs) => {
+                for def in &mut defs {
+                    self.ids.visit_stmt_mut(def);
+                }
+                let mut new = Hir::from_defs(defs);
+
+                // Revert the toplevel when the definitions are rejected.
+                let snapshot = self.toplevel.borrow().clone();
+                let diagnostics =
+                    sema::analyze_defs(&mut new, self.toplevel.clone(), &mut LintLevels::new());
+                emitter().with_output(stdout).emit(&diagnostics);
+                if diagnostics.count() == 0 {
+                    self.hir.defs.append(&mut new.defs);
+                    self.hir.resolutions.append(&mut new.resolutions);
+                    self.hir.scopes.append(&mut new.scopes);
+                    self.hir.entities.append(&mut new.entities);
+                } else {
+                    *self.toplevel.borrow_mut() = snapshot;
+                }
+            }
+            Line::Expr(mut expr) => {
+                self.ids.visit_expr_mut(&mut expr);
+                if !self.analyze_expr(&expr, emitter().with_output(stdout)) {
+                    return Ok(Status::Done);
+                }
+                match interp::eval(&self.hir, &mut self.globals, &expr, stdin, stdout) {
+                    Ok(Value::Void) => {}
+                    Ok(value) => writeln!(stdout, "{}", value)?,
+                    Err(fault) => emitter().with_output(stdout).emit(&fault),
+                }
+            }
+        }
+        Ok(Status::Done)
+    }
+
+    fn type_of(
+        &mut self,
+        input: &str,
+        offset: usize,
+        stdout: &mut dyn Write,
+    ) -> anyhow::Result<Status> {
+        let mut expr = match self.parse(input, offset, stdout) {
+            Ok(Line::Expr(expr)) => expr,
+            Ok(Line::Defs(_)) => {
+                writeln!(stdout, "`:type` expects an expression")?;
+                return Ok(Status::Done);
+            }
+            Err(status) => return Ok(status),
+        };
+        let db = self.db(input);
+        self.ids.visit_expr_mut(&mut expr);
+        if !self.analyze_expr(
+            &expr,
+            Emitter::new(ErrorFormat::Human, &db).with_output(stdout),
+        ) {
+            return Ok(Status::Done);
+        }
+
+        match self.hir.type_of(&expr) {
+            Some(ty) => writeln!(stdout, "{}", ty)?,
+            None => writeln!(stdout, "unknown")?,
+        }
+        Ok(Status::Done)
+    }
+
+    fn ast(
+        &mut self,
+        input: &str,
+        offset: usize,
+        stdout: &mut dyn Write,
+    ) -> anyhow::Result<Status> {
+        match self.parse(input, offset, stdout) {
+            Ok(line) => writeln!(stdout, "{:#?}", line)?,
+            Err(status) => return Ok(status),
+        }
+        Ok(Status::Done)
+    }
+
+    /// Analyzes `expr` against the definitions so far, emitting the diagnostics, and returns
+    /// whether it has no errors.
+    fn analyze_expr(&mut self, expr: &Spanned<Expr>, mut emitter: Emitter) -> bool {
+        let diagnostics = sema::analyze_expr(
+            &mut self.hir,
+            expr,
+            self.toplevel.clone(),
+            &LintLevels::new(),
+        );
+        emitter.emit(&diagnostics);
+        diagnostics.count() == 0
+    }
+
+    /// Returns the session so far followed by `input` as a source for diagnostics.
+    fn db(&self, input: &str) -> SourceDb {
+        SourceDb::new().with_source(SrcId::from_path(SRC), self.code.clone() + input)
+    }
+
+    /// Parses `input[offset..]`, or emits errors unless the input is incomplete.
+    fn parse(&self, input: &str, offset: usize, stdout: &mut dyn Write) -> Result<Line, Status> {
+        let start = self.code.len() + offset;
+        match matc_parser::parse_line(SRC, &input[offset..], start) {
+            Ok(line) => Ok(line),
+            Err(errors) => {
+                // Wait for more lines when the input is only cut off, e.g. by unclosed braces or
+                // a trailing operator, but not on errors such as an overflowing literal at the end.
+                let incomplete = !errors.is_empty()
+                    && errors.iter().all(|err| {
+                        err.found().is_none()
+                            && matches!(
+                                err.reason(),
+                                SimpleReason::Unexpected | SimpleReason::Unclosed { .. }
+                            )
+                    });
+                if !incomplete {
+                    Emitter::new(ErrorFormat::Human, &self.db(input))
+                        .with_output(stdout)
+                        .emit(&errors);
+                    return Err(Status::Done);
+                }
+                Err(Status::Incomplete)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_script(script: &str) -> String {
+        let mut output = Vec::new();
+        run(&mut script.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_eval() {
+        assert_eq!(
+            run_script(
+                r#"let x: i32 = 40;
+fn add(a: i32, b: i32) -> i32 {
+    return a + b;
+}
+add(x, 2)
+'a' as i32 + 1;
+"#
+            ),
+            ">> >> .. .. >> 42\n>> 98\n>> \n"
+        );
+    }
+
+    #[test]
+    fn test_globals() {
+        assert_eq!(
+            run_script(
+                r#"let g: i32 = 0;
+fn inc() -> i32 {
+    g += 1;
+    return g;
+}
+inc()
+inc()
+g
+let h: i32 = 10;
+g + h
+"#
+            ),
+            ">> >> .. .. .. >> 1\n>> 2\n>> 2\n>> >> 12\n>> \n"
+        );
+    }
+
+    #[test]
+    fn test_incomplete() {
+        let output = run_script("1 +\n2\n(1 +\n2\n) * 3\n1 $ 2 +\n99999999999\n");
+        assert!(output.starts_with(">> .. 3\n>> .. .. 9\n>> "));
+        // Neither a syntax error nor an overflowing literal at the end waits for more lines.
+        assert_eq!(output.matches(PROMPT_CONTINUED).count(), 3);
+        assert!(output.contains("[E0001] Error:"));
+        assert!(output.contains("[E0003] Error:"));
+        assert!(output.ends_with(">> \n"));
+    }
+
+    #[test]
+    fn test_commands() {
+        let output = run_script(
+            r#"let c: char = 'a';
+:type c + c
+:type c + 1
+:type putchar(c)
+:reset
+:type c
+c
+"#,
+        );
+        assert!(output.starts_with(">> >> char\n>> i32\n>> i32\n>> >> "));
+        assert_eq!(output.matches("[E0102] Error:").count(), 2);
+        assert!(output.contains("Unresolved reference"));
+        assert!(output.ends_with(">> \n"));
+    }
+
+    #[test]
+    fn test_fault() {
+        let output = run_script("let x: i32 = 0;\n1 / x\nx + 1\n");
+        assert!(output.starts_with(">> >> "));
+        assert!(output.contains("Division by zero"));
+        assert!(output.ends_with(">> 1\n>> \n"));
+    }
+}
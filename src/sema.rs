@@ -1,30 +1,111 @@
+mod confusable_checker;
+mod control_flow_checker;
 mod dereference_checker;
-mod diag;
+pub(crate) mod diag;
 pub(crate) mod entity;
+mod lint_collector;
 mod local_resolver;
 pub(crate) mod scope;
-mod visitor;
+mod type_checker;
 
-use crate::diag::Emit;
+use crate::diag::lint::LintLevels;
+use crate::diag::{Diagnostic, Emit};
 use crate::hir::Hir;
 use crate::sema::diag::Diagnostics;
+use confusable_checker::ConfusableChecker;
+use control_flow_checker::ControlFlowChecker;
 use dereference_checker::DereferenceChecker;
+use lint_collector::LintCollector;
 use local_resolver::LocalResolver;
-use matc_ast::Ast;
+use matc_ast::{Ast, Expr};
+use matc_span::Spanned;
+use scope::Scope;
+use std::cell::RefCell;
+use std::rc::Rc;
+use type_checker::TypeChecker;
 
-pub(crate) fn analyze(ast: Ast, code: &str) -> Result<Hir, Box<dyn Emit>> {
+/// Analyzes `ast` pass by pass until a pass finds errors.
+pub(crate) fn analyze(ast: Ast, levels: &mut LintLevels) -> (Hir, Vec<Diagnostic>) {
     let mut hir = Hir::from(ast);
-    let handle_diag = |diag: Diagnostics| -> Result<(), Box<dyn Emit>> {
-        diag.warnings.emit(code);
-        if diag.has_err() {
-            Err(Box::new(diag.errors))
-        } else {
-            Ok(())
-        }
-    };
+    let diagnostics = run_passes(&mut hir, None, levels, false);
+    (hir, diagnostics)
+}
+
+/// Runs every pass regardless of errors to collect as many diagnostics as possible, e.g. for
+/// editors.
+pub(crate) fn check(ast: Ast, mut levels: LintLevels) -> (Hir, Vec<Diagnostic>) {
+    let mut hir = Hir::from(ast);
+    let diagnostics = run_passes(&mut hir, None, &mut levels, true);
+    (hir, diagnostics)
+}
+
+/// Analyzes new definitions of the interactive mode on the persistent `toplevel`, which holds
+/// the previous definitions.
+pub(crate) fn analyze_defs(
+    defs: &mut Hir,
+    toplevel: Rc<RefCell<Scope>>,
+    levels: &mut LintLevels,
+) -> Vec<Diagnostic> {
+    run_passes(defs, Some(toplevel), levels, false)
+}
+
+/// Analyzes an expression of the interactive mode on the persistent `toplevel`, adding its
+/// resolutions to `hir`.
+pub(crate) fn analyze_expr(
+    hir: &mut Hir,
+    expr: &Spanned<Expr>,
+    toplevel: Rc<RefCell<Scope>>,
+    levels: &LintLevels,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let resolved = LocalResolver::new().resolve_expr(hir, expr, toplevel);
+    let _ = add_pass(&mut diagnostics, resolved, levels)
+        && add_pass(
+            &mut diagnostics,
+            DereferenceChecker::new(hir).check_expr(expr),
+            levels,
+        )
+        && add_pass(
+            &mut diagnostics,
+            TypeChecker::new(hir).check_expr(expr),
+            levels,
+        );
+    diagnostics
+}
 
-    handle_diag(LocalResolver::new().resolve(&mut hir))?;
-    handle_diag(DereferenceChecker::new(&hir).check())?;
+/// Runs the passes in order on `hir`, which is resolved on `toplevel` if any. Unless
+/// `keep_going`, it stops after a pass with errors since the later passes rely on the earlier.
+fn run_passes(
+    hir: &mut Hir,
+    toplevel: Option<Rc<RefCell<Scope>>>,
+    levels: &mut LintLevels,
+    keep_going: bool,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut pass = |diag: Diagnostics, levels: &LintLevels| {
+        add_pass(&mut diagnostics, diag, levels) || keep_going
+    };
+    let lints = LintCollector::new(hir).collect(levels);
+    if !pass(lints, levels) {
+        return diagnostics;
+    }
+    let resolved = match toplevel {
+        Some(toplevel) => LocalResolver::new().resolve_on(hir, toplevel),
+        None => LocalResolver::new().resolve(hir),
+    };
+    let _ = pass(resolved, levels)
+        && pass(DereferenceChecker::new(hir).check(), levels)
+        && pass(TypeChecker::new(hir).check(), levels)
+        && pass(ControlFlowChecker::new(hir).check(), levels)
+        && pass(ConfusableChecker::new(hir).check(), levels);
+    diagnostics
+}
 
-    Ok(hir)
+/// Adds the diagnostics of a pass with the lint levels applied, and returns whether it has
+/// found no errors including the warnings promoted to errors.
+fn add_pass(diagnostics: &mut Vec<Diagnostic>, diag: Diagnostics, levels: &LintLevels) -> bool {
+    let new = levels.apply(diag.diagnostics());
+    let ok = new.count() == 0;
+    diagnostics.extend(new);
+    ok
 }
@@ -0,0 +1,163 @@
+use crate::hir::Hir;
+use crate::sema::diag::{Diagnostics, Warning};
+use matc_ast::visit::{walk_param, walk_stmt, Visitor};
+use matc_ast::{Param, Stmt};
+use matc_span::Spanned;
+use std::ops::Deref;
+
+/// Letters looking the same as ASCII ones, a subset of Unicode's confusables
+const CONFUSABLES: &[(char, char)] = &[
+    // Cyrillic
+    ('а', 'a'),
+    ('с', 'c'),
+    ('ԁ', 'd'),
+    ('е', 'e'),
+    ('һ', 'h'),
+    ('і', 'i'),
+    ('ј', 'j'),
+    ('о', 'o'),
+    ('р', 'p'),
+    ('ԛ', 'q'),
+    ('ѕ', 's'),
+    ('ԝ', 'w'),
+    ('х', 'x'),
+    ('у', 'y'),
+    ('А', 'A'),
+    ('В', 'B'),
+    ('С', 'C'),
+    ('Е', 'E'),
+    ('Н', 'H'),
+    ('І', 'I'),
+    ('Ј', 'J'),
+    ('К', 'K'),
+    ('М', 'M'),
+    ('О', 'O'),
+    ('Р', 'P'),
+    ('Ѕ', 'S'),
+    ('Т', 'T'),
+    ('Х', 'X'),
+    ('У', 'Y'),
+    // Greek
+    ('α', 'a'),
+    ('ι', 'i'),
+    ('ν', 'v'),
+    ('ο', 'o'),
+    ('Α', 'A'),
+    ('Β', 'B'),
+    ('Ε', 'E'),
+    ('Ζ', 'Z'),
+    ('Η', 'H'),
+    ('Ι', 'I'),
+    ('Κ', 'K'),
+    ('Μ', 'M'),
+    ('Ν', 'N'),
+    ('Ο', 'O'),
+    ('Ρ', 'P'),
+    ('Τ', 'T'),
+    ('Υ', 'Y'),
+    ('Χ', 'X'),
+];
+
+/// Maps each character of `name` to the ASCII one it looks like, if any.
+fn skeleton(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            // Fullwidth forms
+            '\u{ff10}'..='\u{ff19}'
+            | '\u{ff21}'..='\u{ff3a}'
+            | '\u{ff3f}'
+            | '\u{ff41}'..='\u{ff5a}' => char::from_u32(c as u32 - 0xfee0).unwrap(),
+            _ => CONFUSABLES
+                .iter()
+                .find(|(confusable, _)| *confusable == c)
+                .map_or(c, |(_, ascii)| *ascii),
+        })
+        .collect()
+}
+
+/// Warns on definitions whose names look like but differ from another, e.g. Cyrillic `а` and
+/// Latin `a`.
+pub(crate) struct ConfusableChecker<'a> {
+    hir: &'a Hir,
+    /// Names defined so far with their skeletons
+    names: Vec<(&'a Spanned<String>, String)>,
+    diag: Diagnostics,
+}
+
+impl<'a> ConfusableChecker<'a> {
+    pub(crate) fn new(hir: &'a Hir) -> Self {
+        Self {
+            hir,
+            names: Vec::new(),
+            diag: Diagnostics::new(),
+        }
+    }
+
+    pub(crate) fn check(&mut self) -> Diagnostics {
+        for def in &self.hir.defs {
+            self.visit_stmt(def);
+        }
+        self.diag.clone()
+    }
+
+    fn define(&mut self, name: &'a Spanned<String>) {
+        let skeleton = skeleton(name);
+        let confusable = self.names.iter().find(|(other, other_skeleton)| {
+            *other_skeleton == skeleton && other.value != name.value
+        });
+        if let Some((other, _)) = confusable {
+            self.diag.push_warn(Warning::ConfusableIdent(
+                name.span,
+                other.span,
+                other.value.to_string(),
+            ));
+        }
+        self.names.push((name, skeleton));
+    }
+}
+
+impl<'a> Visitor<'a> for ConfusableChecker<'a> {
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        if let Stmt::DefFn { name, .. } | Stmt::DefVar { name, .. } = stmt.deref() {
+            self.define(name);
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_param(&mut self, param: &'a Param) {
+        self.define(&param.name);
+        walk_param(self, param);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(code: &str) -> Vec<Warning> {
+        let hir = Hir::from(matc_parser::parse("test.mat", code).unwrap());
+        ConfusableChecker::new(&hir).check().warnings
+    }
+
+    #[test]
+    fn test_skeleton() {
+        assert_eq!(skeleton("раѕѕ"), "pass");
+        assert_eq!(skeleton("ＡＢＣ＿１"), "ABC_1");
+        assert_eq!(skeleton("変数"), "変数");
+    }
+
+    #[test]
+    fn test_check() {
+        // The second `a` is Cyrillic.
+        let warnings = check("let a: i32 = 1;\nfn f(а: i32) -> i32 {\n    return а;\n}\n");
+        assert!(matches!(
+            warnings[..],
+            [Warning::ConfusableIdent(_, _, ref name)] if name == "a"
+        ));
+
+        assert!(check(
+            "let 変数: i32 = 1;\nfn f(a: i32) -> i32 {\n    let a: i32 = 1;\n    return a;\n}\n"
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::hir::Hir;
+use crate::sema::diag::{Diagnostics, Error, Warning};
+use matc_ast::{Stmt, Type};
+use matc_span::{Span, Spanned};
+use std::ops::Deref;
+
+pub(crate) struct ControlFlowChecker<'a> {
+    hir: &'a Hir,
+    diag: Diagnostics,
+}
+
+impl<'a> ControlFlowChecker<'a> {
+    pub(crate) fn new(hir: &'a Hir) -> Self {
+        Self {
+            hir,
+            diag: Diagnostics::new(),
+        }
+    }
+
+    pub(crate) fn check(&mut self) -> Diagnostics {
+        for def in &self.hir.defs {
+            if let Stmt::DefFn {
+                name, ret_ty, body, ..
+            } = def.deref()
+            {
+                let terminator = self.visit_stmt(body);
+                // A `return` may be lost in a statement which failed to parse.
+                if terminator.is_none()
+                    && *ret_ty.value != Type::Void
+                    && !ret_ty.has_error()
+                    && !has_error(body)
+                {
+                    self.diag
+                        .push_err(Error::MissingReturn(name.span, ret_ty.span));
+                }
+            }
+        }
+
+        self.diag.clone()
+    }
+
+    /// Returns the span of the statement terminating the control flow, if any.
+    // TODO: `break` and `continue` also terminate once loops exist.
+    fn visit_stmt(&mut self, stmt: &Spanned<Stmt>) -> Option<Span> {
+        match stmt.deref() {
+            Stmt::Return(_) => Some(stmt.span),
+            Stmt::Block(stmts) => self.visit_block(stmts),
+            Stmt::If { then, els, .. } => {
+                let then = self.visit_stmt(then);
+                let els = els.as_ref().and_then(|els| self.visit_stmt(els));
+                // Terminates only when every branch terminates.
+                then.and(els).map(|_| stmt.span)
+            }
+            _ => None,
+        }
+    }
+
+    fn visit_block(&mut self, stmts: &[Spanned<Stmt>]) -> Option<Span> {
+        let mut terminator = None;
+        for (i, stmt) in stmts.iter().enumerate() {
+            if let Some(terminator) = terminator {
+                self.check_unreachable(&stmts[i..], terminator);
+                break;
+            }
+            terminator = self.visit_stmt(stmt);
+        }
+        terminator
+    }
+
+    fn check_unreachable(&mut self, stmts: &[Spanned<Stmt>], terminator: Span) {
+        let unreachable = stmts
+            .iter()
+            .filter(|stmt| *stmt.value != Stmt::Empty)
+            .map(|stmt| stmt.span)
+            .reduce(Span::union);
+        if let Some(span) = unreachable {
+            self.diag
+                .push_warn(Warning::UnreachableCode(span, terminator));
+        }
+    }
+}
+
+/// Returns whether `stmt` contains a statement which failed to parse.
+fn has_error(stmt: &Spanned<Stmt>) -> bool {
+    match stmt.deref() {
+        Stmt::Error => true,
+        Stmt::Block(stmts) => stmts.iter().any(has_error),
+        Stmt::If { then, els, .. } => has_error(then) || els.as_ref().is_some_and(has_error),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matc_ast::Expr;
+
+    fn defn(ret_ty: Type, body: Vec<Spanned<Stmt>>) -> Spanned<Stmt> {
+        Spanned::any(Stmt::DefFn {
+            attrs: vec![],
+            name: Spanned::any("f".to_string()),
+            args: vec![],
+            ret_ty: Spanned::any(ret_ty),
+            body: Spanned::any(Stmt::Block(body)),
+        })
+    }
+
+    fn ret() -> Spanned<Stmt> {
+        Spanned::any(Stmt::Return(Some(Spanned::any(Expr::I32(1)))))
+    }
+
+    fn if_stmt(then: Vec<Spanned<Stmt>>, els: Option<Vec<Spanned<Stmt>>>) -> Spanned<Stmt> {
+        Spanned::any(Stmt::If {
+            cond: Spanned::any(Expr::Variable("x".to_string())),
+            then: Spanned::any(Stmt::Block(then)),
+            els: els.map(|els| Spanned::any(Stmt::Block(els))),
+        })
+    }
+
+    fn check(defs: Vec<Spanned<Stmt>>) -> Diagnostics {
+        ControlFlowChecker::new(&Hir::from_defs(defs)).check()
+    }
+
+    #[test]
+    fn test_missing_return() {
+        assert_eq!(check(vec![defn(Type::Void, vec![])]), Diagnostics::new());
+        assert_eq!(
+            check(vec![defn(Type::I32, vec![ret()])]),
+            Diagnostics::new()
+        );
+        assert_eq!(
+            check(vec![defn(Type::I32, vec![])]),
+            Diagnostics {
+                warnings: vec![],
+                errors: vec![Error::MissingReturn(Span::any(), Span::any())],
+            },
+        );
+        // fn f() -> i32 { if x { return 1; } }
+        assert_eq!(
+            check(vec![defn(Type::I32, vec![if_stmt(vec![ret()], None)])]),
+            Diagnostics {
+                warnings: vec![],
+                errors: vec![Error::MissingReturn(Span::any(), Span::any())],
+            },
+        );
+        // fn f() -> i32 { if x { return 1; } else { return 1; } }
+        assert_eq!(
+            check(vec![defn(
+                Type::I32,
+                vec![if_stmt(vec![ret()], Some(vec![ret()]))]
+            )]),
+            Diagnostics::new(),
+        );
+        // fn f() -> i32 { { return 1; } }
+        assert_eq!(
+            check(vec![defn(
+                Type::I32,
+                vec![Spanned::any(Stmt::Block(vec![ret()]))]
+            )]),
+            Diagnostics::new(),
+        );
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        assert_eq!(
+            check(vec![defn(Type::I32, vec![ret(), ret(), ret()])]),
+            Diagnostics {
+                warnings: vec![Warning::UnreachableCode(Span::any(), Span::any())],
+                errors: vec![],
+            },
+        );
+        assert_eq!(
+            check(vec![defn(
+                Type::Void,
+                vec![ret(), Spanned::any(Stmt::Empty)]
+            )]),
+            Diagnostics::new(),
+        );
+        assert_eq!(
+            check(vec![defn(
+                Type::I32,
+                vec![if_stmt(vec![ret(), ret()], Some(vec![ret()])), ret()]
+            )]),
+            Diagnostics {
+                warnings: vec![
+                    Warning::UnreachableCode(Span::any(), Span::any()),
+                    Warning::UnreachableCode(Span::any(), Span::any()),
+                ],
+                errors: vec![],
+            },
+        );
+    }
+}
@@ -1,6 +1,8 @@
-use crate::hir::{DefinedVariable, Hir};
+use crate::builtin;
+use crate::hir::{DefinedVariable, Hir, Res};
 use crate::sema::diag::{Diagnostics, Error};
-use matc_ast::{Expr, Stmt};
+use matc_ast::visit::{walk_expr, Visitor};
+use matc_ast::{Expr, Type};
 use matc_span::Spanned;
 use std::ops::Deref;
 
@@ -22,106 +24,115 @@ impl<'a> DereferenceChecker<'a> {
             self.check_toplevel_variable(var);
         }
         for fun in self.hir.defined_functions() {
-            if let Err(err) = self.visit_stmt(fun.body) {
-                self.diag.push_err(err);
-            }
+            self.visit_stmt(fun.body);
         }
 
         self.diag.clone()
     }
 
+    pub(crate) fn check_expr(&mut self, expr: &Spanned<Expr>) -> Diagnostics {
+        self.visit_expr(expr);
+        self.diag.clone()
+    }
+
     // Toplevel variables should be constants
     // TODO: test(not_constant.mat)
     fn check_toplevel_variable(&mut self, var: DefinedVariable) {
-        self.check_variable(&var);
-        if let Err(span) = var.is_constant() {
+        if let Some(expr) = var.expr {
+            self.visit_expr(expr);
+        }
+        if let Err(span) = var.is_constant(self.hir) {
             self.diag.push_err(Error::NotConstant(span));
         }
     }
 
-    fn check_variable(&mut self, var: &DefinedVariable) {
-        if let Some(expr) = var.expr {
-            if let Err(err) = self.visit_expr(expr) {
-                self.diag.push_err(err);
+    /// Returns the number of parameters of the callee, or `None` if it is not callable.
+    fn arity(&self, callee: &Spanned<Expr>) -> Option<usize> {
+        match (callee.deref(), self.hir.resolutions.get(&callee.id)) {
+            (Expr::Variable(name), Some(Res::Builtin)) => {
+                builtin::lookup(name).map(|builtin| builtin.arity)
             }
+            _ => match self.hir.type_of(callee)? {
+                Type::Fn(fn_ty) => Some(fn_ty.params.len()),
+                _ => None,
+            },
         }
     }
+}
 
-    fn visit_stmt(&mut self, stmt: &Spanned<Stmt>) -> Result<(), Error> {
-        match stmt.deref() {
-            Stmt::DefVar {
-                is_mut, name, expr, ..
-            } => {
-                self.check_variable(&DefinedVariable {
-                    is_mut: *is_mut,
-                    name,
-                    expr,
-                });
-                if let Some(expr) = expr {
-                    self.visit_expr(expr)?;
-                }
-            }
-            Stmt::Block(stmts) => {
-                for stmt in stmts {
-                    self.visit_stmt(stmt)?;
-                }
-            }
-            // TODO: Check assignablity to lhs of `Stmt::*Assign`
-            _ => {}
-        }
-        Ok(())
-    }
+impl<'a> Visitor<'a> for DereferenceChecker<'_> {
+    // TODO: Check assignablity to lhs of `Stmt::*Assign`
 
     // TODO: test(not_callable.mat)
-    fn visit_expr(&self, expr: &Spanned<Expr>) -> Result<(), Error> {
-        match expr.deref() {
-            Expr::FnCall { name, args } => {
-                for arg in args {
-                    self.visit_expr(arg)?;
+    fn visit_expr(&mut self, expr: &'a Spanned<Expr>) {
+        if let Expr::FnCall { name, args } = expr.deref() {
+            let reported = matches!(name.deref(), Expr::Variable(_))
+                && !self.hir.resolutions.contains_key(&name.id)
+                || self.hir.type_of(name) == Some(Type::Error);
+            match self.arity(name) {
+                Some(arity) if arity != args.len() => {
+                    self.diag
+                        .push_err(Error::ArgCountMismatch(expr.span, arity, args.len()))
                 }
-                if !self.is_callable(name) {
-                    return Err(Error::NotCallable(name.span));
-                }
-            }
-            Expr::Or(lhs, rhs)
-            | Expr::And(lhs, rhs)
-            | Expr::Lt(lhs, rhs)
-            | Expr::Gt(lhs, rhs)
-            | Expr::Lte(lhs, rhs)
-            | Expr::Gte(lhs, rhs)
-            | Expr::Eq(lhs, rhs)
-            | Expr::Neq(lhs, rhs)
-            | Expr::BitOr(lhs, rhs)
-            | Expr::BitXor(lhs, rhs)
-            | Expr::BitAnd(lhs, rhs)
-            | Expr::Shl(lhs, rhs)
-            | Expr::Shr(lhs, rhs)
-            | Expr::Add(lhs, rhs)
-            | Expr::Sub(lhs, rhs)
-            | Expr::Mul(lhs, rhs)
-            | Expr::Div(lhs, rhs)
-            | Expr::Rem(lhs, rhs) => {
-                self.visit_expr(rhs)?;
-                self.visit_expr(lhs)?;
+                Some(_) => {}
+                // Unresolved names are reported by `LocalResolver`, and unknown types by the
+                // parser.
+                None if reported => {}
+                None => self.diag.push_err(Error::NotCallable(name.span)),
             }
-            Expr::As(expr, _) => {
-                self.visit_expr(expr)?;
-            }
-            _ => {}
         }
-        Ok(())
+        walk_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sema::local_resolver::LocalResolver;
+
+    fn check(code: &str) -> Vec<Error> {
+        let mut hir = Hir::from(matc_parser::parse("test.mat", code).unwrap());
+        LocalResolver::new().resolve(&mut hir);
+        DereferenceChecker::new(&hir).check().errors
     }
 
-    fn is_callable(&self, expr: &Spanned<Expr>) -> bool {
-        if let Expr::Variable(var_name) = expr.deref() {
-            for def in &self.hir.defs {
-                if let Stmt::DefFn { name, .. } = def.deref() {
-                    if var_name == name.deref() {
-                        return true;
-                    }
+    #[test]
+    fn test_not_callable() {
+        let code = "let x: i32 = 1;\nfn f() -> i32 {\n    if 1 {\n        return x(1);\n    }\n    return f()(2);\n}\n";
+        let errors = check(code);
+        // `x`, and `f()` which returns an `i32`
+        assert_eq!(errors.len(), 2);
+        assert_eq!(&code[errors[0].span().range()], "x");
+        assert!(check("fn f() -> i32 {\n    return f() + putchar(1);\n}\n").is_empty());
+
+        // The local shadows the function.
+        let code = "fn f() -> i32 {\n    let f: i32 = 1;\n    return f();\n}\n";
+        let errors = check(code);
+        assert!(matches!(errors[..], [Error::NotCallable(_)]));
+        assert_eq!(&code[errors[0].span().range()], "f");
+    }
+
+    #[test]
+    fn test_arg_count() {
+        let code = "fn f(a: i32, b: i32) -> i32 {\n    return f(a) + putchar(a, b);\n}\n";
+        let errors: Vec<_> = check(code)
+            .into_iter()
+            .map(|err| match err {
+                Error::ArgCountMismatch(span, expected, found) => {
+                    (span.range().start, expected, found)
                 }
-            }
-        }
-        false
+                err => panic!("unexpected error: {:?}", err),
+            })
+            .collect();
+        let (call_f, call_putchar) = (code.find("f(a)").unwrap(), code.find("putchar").unwrap());
+        assert_eq!(errors, vec![(call_f, 2, 1), (call_putchar, 1, 2)]);
+        assert!(check("fn f(a: i32) -> i32 {\n    return f(f(a));\n}\n").is_empty());
+
+        // Calls through variables are checked against their types.
+        let code = "fn f(g: fn(i32) -> fn() -> i32) -> i32 {\n    return g(1)(2);\n}\n";
+        assert!(matches!(
+            check(code)[..],
+            [Error::ArgCountMismatch(_, 0, 1)]
+        ));
     }
 }
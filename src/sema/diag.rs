@@ -1,5 +1,7 @@
-use crate::diag::{emit, Diagnostics as Diag, Emit};
-use ariadne::{Color, Fmt, Label, Report, ReportKind, Source};
+use crate::diag::{Diagnostic, Diagnostics as Diag, Emit, Label};
+use crate::util::pluralize;
+use ariadne::Color;
+use matc_ast::Type;
 use matc_span::Span;
 use std::fmt::Debug;
 
@@ -8,20 +10,93 @@ pub(crate) enum Warning {
     // LocalResolver
     UnusedEntity(Span),
     // TypeResolver
+    // ControlFlowChecker
+    UnreachableCode(Span, Span),
+    // LintCollector
+    UnknownLint(Span),
+    // ConfusableChecker
+    /// With the definition and the name it looks like
+    ConfusableIdent(Span, Span, String),
 }
 
-impl Emit for Warning {
-    fn emit(&self, code: &str) {
+impl Warning {
+    pub(crate) fn span(&self) -> Span {
         match *self {
+            Warning::UnusedEntity(span)
+            | Warning::UnreachableCode(span, _)
+            | Warning::UnknownLint(span)
+            | Warning::ConfusableIdent(span, ..) => span,
+        }
+    }
+
+    pub(crate) fn message(&self) -> &'static str {
+        match self {
+            Warning::UnusedEntity(_) => "Unused entity",
+            Warning::UnreachableCode(..) => "Unreachable code",
+            Warning::UnknownLint(_) => "Unknown lint",
+            Warning::ConfusableIdent(..) => "Confusable identifier",
+        }
+    }
+
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Warning::UnusedEntity(_) => "W0001",
+            Warning::UnreachableCode(..) => "W0002",
+            Warning::UnknownLint(_) => "W0003",
+            Warning::ConfusableIdent(..) => "W0004",
+        }
+    }
+
+    /// The name to control the level with, e.g. `#[allow(unused)]`
+    pub(crate) fn lint(&self) -> &'static str {
+        match self {
+            Warning::UnusedEntity(_) => "unused",
+            Warning::UnreachableCode(..) => "unreachable_code",
+            Warning::UnknownLint(_) => "unknown_lints",
+            Warning::ConfusableIdent(..) => "confusable_idents",
+        }
+    }
+}
+
+impl Emit for Warning {
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let diagnostic = Diagnostic::warning(self.span(), self.message())
+            .with_code(self.code())
+            .with_lint(self.lint());
+        let diagnostic = match *self {
+            Warning::ConfusableIdent(span, other_span, ref other) => diagnostic
+                .with_label(
+                    Label::new(other_span)
+                        .with_message(format!("`{}` is defined here", other))
+                        .with_color(Color::Blue),
+                )
+                .with_label(
+                    Label::new(span)
+                        .with_message(format!("this looks like `{}`", other))
+                        .with_color(Color::Yellow),
+                )
+                .with_help("rename either of them so that they can be told apart"),
             Warning::UnusedEntity(span) => {
-                Report::build(ReportKind::Warning, span)
-                    .with_message("Unused entity")
-                    .with_label(Label::new(span).with_color(Color::Yellow))
-                    .finish()
-                    .print((span.src(), Source::from(code)))
+                diagnostic.with_label(Label::new(span).with_color(Color::Yellow))
             }
-        }
-        .unwrap();
+            Warning::UnreachableCode(span, terminator) => diagnostic
+                .with_label(
+                    Label::new(terminator)
+                        .with_message("any code following this statement is unreachable")
+                        .with_color(Color::Blue),
+                )
+                .with_label(
+                    Label::new(span)
+                        .with_message("unreachable statement")
+                        .with_color(Color::Yellow),
+                ),
+            Warning::UnknownLint(span) => diagnostic.with_label(
+                Label::new(span)
+                    .with_message("no lint has this name")
+                    .with_color(Color::Yellow),
+            ),
+        };
+        vec![diagnostic]
     }
 }
 
@@ -29,60 +104,135 @@ impl Emit for Warning {
 pub(crate) enum Error {
     // LocalResolver
     DuplicatedDef(Span, Span),
-    UnresolvedRef(Span),
+    /// With a help suggesting what it may refer to
+    UnresolvedRef(Span, Option<String>),
     // DereferenceChecker
     NotConstant(Span),
     NotCallable(Span),
+    /// expected, found
+    ArgCountMismatch(Span, usize, usize),
+    // ControlFlowChecker
+    MissingReturn(Span, Span),
+    // TypeChecker
+    /// expected, and what is found, e.g. "a string"
+    TypeMismatch(Span, Type, String),
+    BuiltinAsValue(Span),
+}
+
+impl Error {
+    pub(crate) fn span(&self) -> Span {
+        match *self {
+            Error::DuplicatedDef(_, span)
+            | Error::UnresolvedRef(span, _)
+            | Error::NotConstant(span)
+            | Error::NotCallable(span)
+            | Error::ArgCountMismatch(span, ..)
+            | Error::MissingReturn(span, _)
+            | Error::TypeMismatch(span, ..)
+            | Error::BuiltinAsValue(span) => span,
+        }
+    }
+
+    pub(crate) fn message(&self) -> &'static str {
+        match self {
+            Error::DuplicatedDef(..) => "Duplicated definition",
+            Error::UnresolvedRef(..) => "Unresolved reference",
+            Error::NotConstant(_) => "Not a constant",
+            Error::NotCallable(_) => "Not callable",
+            Error::ArgCountMismatch(..) => "Argument count mismatch",
+            Error::MissingReturn(..) => "Missing return",
+            Error::TypeMismatch(..) => "Type mismatch",
+            Error::BuiltinAsValue(_) => "Builtin used as a value",
+        }
+    }
+
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            Error::DuplicatedDef(..) => "E0101",
+            Error::UnresolvedRef(..) => "E0102",
+            Error::NotConstant(_) => "E0103",
+            Error::NotCallable(_) => "E0104",
+            Error::ArgCountMismatch(..) => "E0106",
+            Error::MissingReturn(..) => "E0105",
+            Error::TypeMismatch(..) => "E0107",
+            Error::BuiltinAsValue(_) => "E0108",
+        }
+    }
 }
 
 impl Emit for Error {
-    fn emit(&self, code: &str) {
-        let (span, message, labels, notes) = match *self {
-            Error::DuplicatedDef(pre_span, span) => (
-                span,
-                "Duplicated definition",
-                vec![
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let diagnostic = Diagnostic::error(self.span(), self.message()).with_code(self.code());
+        let diagnostic = match *self {
+            Error::DuplicatedDef(pre_span, span) => diagnostic
+                .with_label(
                     Label::new(pre_span)
-                        .with_message("previous definition".fg(Color::Blue))
+                        .with_message("previous definition")
                         .with_color(Color::Blue),
+                )
+                .with_label(
                     Label::new(span)
-                        .with_message("redefined here".fg(Color::Red))
+                        .with_message("redefined here")
                         .with_color(Color::Red),
-                ],
-                vec![],
-            ),
-            Error::UnresolvedRef(span) => (
-                span,
-                "Unresolved reference",
-                vec![Label::new(span)
-                    .with_message("undefined ident".fg(Color::Red))
-                    .with_color(Color::Red)],
-                vec![],
+                ),
+            Error::UnresolvedRef(span, ref help) => {
+                let diagnostic = diagnostic.with_label(
+                    Label::new(span)
+                        .with_message("undefined ident")
+                        .with_color(Color::Red),
+                );
+                match help {
+                    Some(help) => diagnostic.with_help(help),
+                    None => diagnostic,
+                }
+            }
+            Error::NotConstant(span) => diagnostic
+                .with_label(
+                    Label::new(span)
+                        .with_message("this is not a constant")
+                        .with_color(Color::Red),
+                )
+                .with_note("toplevel definitions should be constants"),
+            Error::NotCallable(span) => diagnostic.with_label(
+                Label::new(span)
+                    .with_message("this is not a function")
+                    .with_color(Color::Red),
             ),
-            Error::NotConstant(span) => (
-                span,
-                "Not a constant",
-                vec![Label::new(span)
-                    .with_message("this is not a constant".fg(Color::Red))
-                    .with_color(Color::Red)],
-                vec!["toplevel definitions should be constants".fg(Color::Blue)],
+            Error::ArgCountMismatch(span, expected, found) => diagnostic.with_label(
+                Label::new(span)
+                    .with_message(format!(
+                        "expected {} {}, found {}",
+                        expected,
+                        pluralize("argument", expected),
+                        found
+                    ))
+                    .with_color(Color::Red),
             ),
-            Error::NotCallable(span) => (
-                span,
-                "Not callable",
-                vec![Label::new(span)
-                    .with_message("this is not a function".fg(Color::Red))
-                    .with_color(Color::Red)],
-                vec![],
+            Error::MissingReturn(span, ret_span) => diagnostic
+                .with_label(
+                    Label::new(span)
+                        .with_message("this function may end without returning a value")
+                        .with_color(Color::Red),
+                )
+                .with_label(
+                    Label::new(ret_span)
+                        .with_message("expected a value because of this return type")
+                        .with_color(Color::Blue),
+                ),
+            Error::TypeMismatch(span, ref expected, ref found) => diagnostic.with_label(
+                Label::new(span)
+                    .with_message(format!("expected `{}`, found {}", expected, found))
+                    .with_color(Color::Red),
             ),
+            Error::BuiltinAsValue(span) => diagnostic
+                .with_label(
+                    Label::new(span)
+                        .with_message("this can only be called")
+                        .with_color(Color::Red),
+                )
+                .with_note("builtin functions have no signatures to be stored with"),
         };
-        emit(
-            code,
-            span,
-            message.to_string(),
-            labels,
-            notes.iter().map(ToString::to_string).collect(),
-        );
+        vec![diagnostic]
     }
 }
 
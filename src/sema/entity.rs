@@ -1,16 +1,39 @@
-use matc_ast::{Param, Stmt, Type};
-use matc_span::Spanned;
+use matc_ast::{FnType, Param, Stmt, Type};
+use matc_span::{NodeId, Spanned};
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum EntityKind {
+    Function,
+    Global,
+    Param,
+    Local,
+}
+
+impl fmt::Display for EntityKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntityKind::Function => write!(f, "function"),
+            EntityKind::Global => write!(f, "global"),
+            EntityKind::Param => write!(f, "parameter"),
+            EntityKind::Local => write!(f, "local"),
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct Entity {
+    pub(crate) kind: EntityKind,
     pub(crate) name: Spanned<String>,
+    /// The declared type, or `Type::Fn` of the signature for functions
     pub(crate) ty: Spanned<Type>,
     pub(crate) n_referred: usize,
 }
 
 impl Entity {
-    pub(crate) fn new(name: Spanned<String>, ty: Spanned<Type>) -> Entity {
+    pub(crate) fn new(kind: EntityKind, name: Spanned<String>, ty: Spanned<Type>) -> Entity {
         Entity {
+            kind,
             name,
             ty,
             n_referred: 0,
@@ -19,6 +42,11 @@ impl Entity {
 }
 
 impl Entity {
+    /// The definition ID, which is the node ID of the name
+    pub(crate) fn id(&self) -> NodeId {
+        self.name.id
+    }
+
     pub(crate) fn referred(&mut self) {
         self.n_referred += 1;
     }
@@ -27,13 +55,28 @@ impl Entity {
     }
 }
 
+/// Converts toplevel definitions.
 impl TryFrom<Stmt> for Entity {
     type Error = &'static str;
 
     fn try_from(item: Stmt) -> Result<Self, Self::Error> {
         match item {
-            Stmt::DefVar { name, ty, .. } => Ok(Entity::new(name, ty)),
-            Stmt::DefFn { name, ret_ty, .. } => Ok(Entity::new(name, ret_ty)),
+            Stmt::DefVar { name, ty, .. } => Ok(Entity::new(EntityKind::Global, name, ty)),
+            Stmt::DefFn {
+                name, args, ret_ty, ..
+            } => {
+                let fn_ty = FnType {
+                    params: args.iter().map(|arg| arg.ty.value().clone()).collect(),
+                    ret: Box::new(ret_ty.value().clone()),
+                };
+                // The signature spans from the name to the return type.
+                let span = name.span.union(ret_ty.span);
+                Ok(Entity::new(
+                    EntityKind::Function,
+                    name,
+                    Spanned::new(Type::Fn(fn_ty), span),
+                ))
+            }
             _ => Err("Converting into entity is only permitted to DefVar & DefFn"),
         }
     }
@@ -41,12 +84,11 @@ impl TryFrom<Stmt> for Entity {
 
 impl From<Param> for Entity {
     fn from(param: Param) -> Self {
-        Self::new(param.name, param.ty)
+        Self::new(EntityKind::Param, param.name, param.ty)
     }
 }
 impl From<&Param> for Entity {
     fn from(param: &Param) -> Self {
-        let param = param.clone();
-        Self::new(param.name, param.ty)
+        Self::from(param.clone())
     }
 }
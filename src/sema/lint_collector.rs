@@ -0,0 +1,60 @@
+use crate::diag::lint::{self, Level, LintLevels};
+use crate::hir::Hir;
+use crate::sema::diag::{Diagnostics, Warning};
+use matc_ast::visit::{walk_stmt, Visitor};
+use matc_ast::{Attribute, Stmt};
+use matc_span::{Span, Spanned};
+use std::ops::Deref;
+
+/// Collects lint levels of attributes, scoped to the definitions they are attached to.
+pub(crate) struct LintCollector<'a> {
+    defs: &'a [Spanned<Stmt>],
+    levels: Vec<(Span, String, Level)>,
+    diag: Diagnostics,
+}
+
+impl<'a> LintCollector<'a> {
+    pub(crate) fn new(hir: &'a Hir) -> Self {
+        Self {
+            defs: &hir.defs,
+            levels: Vec::new(),
+            diag: Diagnostics::new(),
+        }
+    }
+
+    pub(crate) fn collect(&mut self, levels: &mut LintLevels) -> Diagnostics {
+        for def in self.defs {
+            self.visit_stmt(def);
+        }
+        for (span, lint, level) in self.levels.drain(..) {
+            levels.push(Some(span), lint, level);
+        }
+        self.diag.clone()
+    }
+
+    fn visit_attrs(&mut self, attrs: &[Spanned<Attribute>], span: Span) {
+        for attr in attrs {
+            let level = match attr.deref() {
+                Attribute::Allow(_) => Level::Allow,
+                Attribute::Warn(_) => Level::Warn,
+                Attribute::Deny(_) => Level::Deny,
+            };
+            for lint in attr.lints() {
+                if lint::is_known(lint) {
+                    self.levels.push((span, lint.value.to_string(), level));
+                } else {
+                    self.diag.push_warn(Warning::UnknownLint(lint.span));
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for LintCollector<'_> {
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        if let Stmt::DefFn { attrs, .. } | Stmt::DefVar { attrs, .. } = stmt.deref() {
+            self.visit_attrs(attrs, stmt.span);
+        }
+        walk_stmt(self, stmt);
+    }
+}
@@ -1,16 +1,22 @@
-use crate::hir::Hir;
-use crate::sema::diag::Diagnostics;
-use crate::sema::entity::Entity;
+use crate::hir::{Hir, Res};
+use crate::sema::diag::{Diagnostics, Error};
+use crate::sema::entity::{Entity, EntityKind};
 use crate::sema::scope::Scope;
+use matc_ast::visit::{walk_expr, walk_stmt, Visitor};
 use matc_ast::{Expr, Stmt};
-use matc_span::Spanned;
+use matc_span::{NodeId, Span, Spanned};
 use std::cell::RefCell;
-use std::collections::LinkedList;
+use std::collections::{BTreeMap, LinkedList};
 use std::ops::Deref;
 use std::rc::Rc;
 
 pub(crate) struct LocalResolver {
     scope_stack: LinkedList<Rc<RefCell<Scope>>>,
+    /// Variables defined in each block being visited, to tell ones referred to too early
+    block_defs: Vec<Vec<Spanned<String>>>,
+    resolutions: BTreeMap<NodeId, Res>,
+    /// Scopes of functions and blocks by the IDs of their statements
+    scopes: BTreeMap<NodeId, Rc<RefCell<Scope>>>,
     diag: Diagnostics,
 }
 
@@ -18,6 +24,9 @@ impl LocalResolver {
     pub(crate) fn new() -> Self {
         Self {
             scope_stack: LinkedList::new(),
+            block_defs: Vec::new(),
+            resolutions: BTreeMap::new(),
+            scopes: BTreeMap::new(),
             diag: Diagnostics::new(),
         }
     }
@@ -31,12 +40,50 @@ impl LocalResolver {
         self.resolve_functions(hir);
         toplevel.borrow().check_references(&mut self.diag);
 
+        self.record(hir, &toplevel);
         hir.set_scope(toplevel);
         // TODO: ast.set_constant_table(constant_table);
 
         self.diag.clone()
     }
 
+    /// Resolves definitions on an existing toplevel, which the interactive mode keeps across
+    /// inputs. Unused entities are not reported since they may be referred to later.
+    pub(crate) fn resolve_on(
+        &mut self,
+        hir: &mut Hir,
+        toplevel: Rc<RefCell<Scope>>,
+    ) -> Diagnostics {
+        self.scope_stack.push_back(toplevel.clone());
+        self.define_entities(hir, toplevel.clone());
+
+        self.resolve_gvar_initializers(hir);
+        self.resolve_functions(hir);
+        self.record(hir, &toplevel);
+
+        self.diag.clone()
+    }
+
+    /// Resolves an expression of the interactive mode, adding its resolutions to `hir`.
+    pub(crate) fn resolve_expr(
+        &mut self,
+        hir: &mut Hir,
+        expr: &Spanned<Expr>,
+        toplevel: Rc<RefCell<Scope>>,
+    ) -> Diagnostics {
+        self.scope_stack.push_back(toplevel.clone());
+        self.visit_expr(expr);
+        self.record(hir, &toplevel);
+        self.diag.clone()
+    }
+
+    /// Stores the resolutions, the scopes and the entities into `hir`.
+    fn record(&mut self, hir: &mut Hir, toplevel: &Rc<RefCell<Scope>>) {
+        hir.resolutions.append(&mut self.resolutions);
+        hir.scopes.append(&mut self.scopes);
+        toplevel.borrow().collect_entities(&mut hir.entities);
+    }
+
     fn define_entities(&mut self, hir: &Hir, toplevel: Rc<RefCell<Scope>>) {
         for entity in hir.definitions() {
             if let Err(err) = toplevel.borrow_mut().define_entity(entity) {
@@ -53,7 +100,7 @@ impl LocalResolver {
         }
     }
 
-    fn resolve_functions(&mut self, hir: &mut Hir) {
+    fn resolve_functions(&mut self, hir: &Hir) {
         for stmt in &hir.defs {
             if let Stmt::DefFn { args, body, .. } = stmt.deref() {
                 self.push_scope();
@@ -69,125 +116,96 @@ impl LocalResolver {
                     // TODO: function args do not seem marked as unused variable
                 }
                 self.visit_stmt(body);
-                self.pop_scope(); // TODO: fn.set_scope(self.pop_scope());
+                let scope = self.pop_scope().unwrap();
+                self.scopes.insert(stmt.id, scope);
             }
         }
     }
 
-    fn visit_expr(&mut self, expr: &Spanned<Expr>) {
+    /// Explains an unresolved `name` at `span` which is defined later in an enclosing block.
+    fn defined_later(&self, name: &str, span: Span) -> Option<String> {
+        let (depth, _) = self.block_defs.iter().rev().enumerate().find(|(_, defs)| {
+            defs.iter()
+                .any(|def| *def.value == name && def.span.range().start > span.range().start)
+        })?;
+        let block = if depth == 0 {
+            "this block"
+        } else {
+            "an enclosing block"
+        };
+        Some(format!("a local `{}` is defined later in {}", name, block))
+    }
+
+    fn push_scope(&mut self) {
+        self.scope_stack
+            .push_back(Scope::new(Some(self.current_scope().clone())));
+    }
+    fn pop_scope(&mut self) -> Option<Rc<RefCell<Scope>>> {
+        self.scope_stack.pop_back()
+    }
+    fn current_scope(&self) -> &Rc<RefCell<Scope>> {
+        self.scope_stack.back().unwrap()
+    }
+}
+
+impl<'a> Visitor<'a> for LocalResolver {
+    fn visit_expr(&mut self, expr: &'a Spanned<Expr>) {
         match expr.deref() {
             Expr::Variable(var) => {
-                let maybe_err = self
-                    .current_scope()
-                    .borrow_mut()
-                    .refer(var, expr.span)
-                    .err();
-
-                // TODO: node.setEntity(ent); when not error; `refer()` should return entity.
-                if let Some(err) = maybe_err {
-                    self.diag.push_err(err);
+                let result = self.current_scope().borrow_mut().refer(var, expr.span);
+                match result {
+                    Ok(res) => {
+                        self.resolutions.insert(expr.id, res);
+                    }
+                    Err(_) => {
+                        let help = self
+                            .defined_later(var, expr.span)
+                            .or_else(|| self.current_scope().borrow().suggest(var));
+                        self.diag.push_err(Error::UnresolvedRef(expr.span, help));
+                    }
                 }
             }
             Expr::String(_str) => {
-                // node.setEntry(constantTable.intern(node.value()));
-                // return null;
-                todo!()
-            }
-            Expr::Or(lhs, rhs)
-            | Expr::And(lhs, rhs)
-            | Expr::Lt(lhs, rhs)
-            | Expr::Gt(lhs, rhs)
-            | Expr::Lte(lhs, rhs)
-            | Expr::Gte(lhs, rhs)
-            | Expr::Eq(lhs, rhs)
-            | Expr::Neq(lhs, rhs)
-            | Expr::BitOr(lhs, rhs)
-            | Expr::BitXor(lhs, rhs)
-            | Expr::BitAnd(lhs, rhs)
-            | Expr::Shl(lhs, rhs)
-            | Expr::Shr(lhs, rhs)
-            | Expr::Add(lhs, rhs)
-            | Expr::Sub(lhs, rhs)
-            | Expr::Mul(lhs, rhs)
-            | Expr::Div(lhs, rhs)
-            | Expr::Rem(lhs, rhs) => {
-                self.visit_expr(rhs);
-                self.visit_expr(lhs);
-            }
-            Expr::As(lhs, _ty) => {
-                self.visit_expr(lhs);
-            }
-            Expr::FnCall { name, args } => {
-                self.visit_expr(name);
-                for arg in args {
-                    self.visit_expr(arg);
-                }
+                // TODO: node.setEntry(constantTable.intern(node.value()));
             }
-            _ => (),
+            _ => walk_expr(self, expr),
         }
     }
 
-    fn visit_stmt(&mut self, stmt: &Spanned<Stmt>) {
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
         match stmt.deref() {
             Stmt::Block(stmts) => {
                 self.push_scope();
-                for s in stmts {
-                    self.visit_stmt(s);
-                }
-                self.pop_scope(); // TODO: stmts.set_scope(self.pop_scope());
+                self.block_defs.push(
+                    stmts
+                        .iter()
+                        .filter_map(|stmt| match stmt.deref() {
+                            Stmt::DefVar { name, .. } => Some(name.clone()),
+                            _ => None,
+                        })
+                        .collect(),
+                );
+                walk_stmt(self, stmt);
+                self.block_defs.pop();
+                let scope = self.pop_scope().unwrap();
+                self.scopes.insert(stmt.id, scope);
             }
-            Stmt::DefVar { name, ty, expr, .. } => {
+            Stmt::DefVar { name, ty, .. } => {
                 // should evaluate expr first to support like `let min = min(1, 2)`.
-                if let Some(expr) = expr {
-                    self.visit_expr(expr);
-                }
+                walk_stmt(self, stmt);
 
                 let maybe_err = self
                     .current_scope()
                     .borrow_mut()
-                    .define_entity(Entity::new(name.clone(), ty.clone()))
+                    .define_entity(Entity::new(EntityKind::Local, name.clone(), ty.clone()))
                     .err();
                 if let Some(err) = maybe_err {
                     self.diag.push_err(err);
                 }
             }
-            Stmt::If { cond, then, els } => {
-                self.visit_expr(cond);
-                self.visit_stmt(then);
-                if let Some(els) = els {
-                    self.visit_stmt(els);
-                }
-            }
-            Stmt::Return(Some(expr)) => self.visit_expr(expr),
-            Stmt::Assign(lhs, rhs)
-            | Stmt::AddAssign(lhs, rhs)
-            | Stmt::SubAssign(lhs, rhs)
-            | Stmt::MulAssign(lhs, rhs)
-            | Stmt::DivAssign(lhs, rhs)
-            | Stmt::RemAssign(lhs, rhs)
-            | Stmt::BitAndAssign(lhs, rhs)
-            | Stmt::BitOrAssign(lhs, rhs)
-            | Stmt::BitXorAssign(lhs, rhs)
-            | Stmt::ShlAssign(lhs, rhs)
-            | Stmt::ShrAssign(lhs, rhs) => {
-                self.visit_expr(rhs);
-                self.visit_expr(lhs);
-            }
-            Stmt::Expr(expr) => self.visit_expr(expr),
-            _ => (),
+            _ => walk_stmt(self, stmt),
         }
     }
-
-    fn push_scope(&mut self) {
-        self.scope_stack
-            .push_back(Scope::new(Some(self.current_scope().clone())));
-    }
-    fn pop_scope(&mut self) -> Option<Rc<RefCell<Scope>>> {
-        self.scope_stack.pop_back()
-    }
-    fn current_scope(&self) -> &Rc<RefCell<Scope>> {
-        self.scope_stack.back().unwrap()
-    }
 }
 
 #[cfg(test)]
@@ -195,10 +213,10 @@ mod tests {
     use super::*;
     use crate::sema::diag::{Error, Warning};
     use matc_ast::Type;
-    use matc_span::Span;
 
     fn let_imut_i8(name: &str, expr: Option<Spanned<Expr>>) -> Spanned<Stmt> {
         Spanned::any(Stmt::DefVar {
+            attrs: vec![],
             is_mut: false,
             name: Spanned::any(name.to_string()),
             ty: Spanned::any(Type::I8),
@@ -239,7 +257,7 @@ mod tests {
             ])),
             Diagnostics {
                 warnings: vec![Warning::UnusedEntity(Span::any())],
-                errors: vec![Error::UnresolvedRef(Span::any())],
+                errors: vec![Error::UnresolvedRef(Span::any(), None)],
             },
         );
         assert_eq!(
@@ -255,6 +273,7 @@ mod tests {
 
         // let foo: i8 = 1 != bar | 2 & buz << 3 || qux;
         let let_complex = Spanned::any(Stmt::DefVar {
+            attrs: vec![],
             is_mut: false,
             name: Spanned::any("foo".to_string()),
             ty: Spanned::any(Type::I8),
@@ -281,9 +300,9 @@ mod tests {
             Diagnostics {
                 warnings: vec![Warning::UnusedEntity(Span::any())],
                 errors: vec![
-                    Error::UnresolvedRef(Span::any()),
-                    Error::UnresolvedRef(Span::any()),
-                    Error::UnresolvedRef(Span::any())
+                    Error::UnresolvedRef(Span::any(), None),
+                    Error::UnresolvedRef(Span::any(), None),
+                    Error::UnresolvedRef(Span::any(), None)
                 ],
             },
         );
@@ -295,8 +314,8 @@ mod tests {
             Diagnostics {
                 warnings: vec![Warning::UnusedEntity(Span::any())],
                 errors: vec![
-                    Error::UnresolvedRef(Span::any()),
-                    Error::UnresolvedRef(Span::any()),
+                    Error::UnresolvedRef(Span::any(), None),
+                    Error::UnresolvedRef(Span::any(), None),
                 ],
             },
         );
@@ -308,7 +327,7 @@ mod tests {
             ])),
             Diagnostics {
                 warnings: vec![Warning::UnusedEntity(Span::any())],
-                errors: vec![Error::UnresolvedRef(Span::any())],
+                errors: vec![Error::UnresolvedRef(Span::any(), None)],
             },
         );
         assert_eq!(
@@ -325,7 +344,72 @@ mod tests {
         );
     }
 
-    // TODO: test (unres_block.mat)
     #[test]
-    fn test_visit_block() {}
+    fn test_visit_block() {
+        let code = include_str!("../../examples/unres_block.mat");
+        let ast = matc_parser::parse("unres_block.mat", code).unwrap();
+        let diag = LocalResolver::new().resolve(&mut Hir::from(ast));
+        let helps: Vec<_> = diag
+            .errors
+            .into_iter()
+            .filter_map(|err| match err {
+                Error::UnresolvedRef(_, help) => Some(help),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            helps,
+            vec![
+                Some("a local `qux` is defined later in this block".to_string()),
+                Some("a local with a similar name exists: `qux`".to_string()),
+                Some("a local `garply` is defined later in this block".to_string()),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_resolutions() {
+        let code = "let x: i32 = 1;\nfn f(x: i32) -> i32 {\n    {\n        let x: i32 = x;\n        putchar(x);\n    }\n    return x;\n}\n";
+        let mut hir = Hir::from(matc_parser::parse("test.mat", code).unwrap());
+        LocalResolver::new().resolve(&mut hir);
+
+        // Each use paired with the start of its definition
+        let references: Vec<_> = hir
+            .references()
+            .into_iter()
+            .map(|(span, entity)| (span.range().start, entity.name.span.range().start))
+            .collect();
+        assert_eq!(references, vec![(65, 21), (84, 56), (105, 21)]);
+        assert_eq!(
+            hir.resolutions
+                .values()
+                .filter(|res| **res == Res::Builtin)
+                .count(),
+            1
+        );
+        // The global, the parameter, the local and `f`
+        assert_eq!(hir.entities.len(), 4);
+    }
+
+    #[test]
+    fn test_scopes() {
+        let code = "fn f(a: i32) -> i32 {\n    let b: i32 = a;\n    if b {\n        let c: i32 = b;\n    }\n    return b;\n}\n";
+        let mut hir = Hir::from(matc_parser::parse("test.mat", code).unwrap());
+        LocalResolver::new().resolve(&mut hir);
+
+        // The function and its two blocks
+        assert_eq!(hir.scopes.len(), 3);
+        assert_eq!(
+            hir.dump_scopes(),
+            "toplevel
+    function f: fn(i32) -> i32 @ test.mat:3..4
+    fn f @ test.mat:0..98
+        parameter a: i32 @ test.mat:5..6
+        block @ test.mat:20..98
+            local b: i32 @ test.mat:30..31
+            block @ test.mat:51..82
+                local c: i32 @ test.mat:65..66
+"
+        );
+    }
 }
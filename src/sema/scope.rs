@@ -1,11 +1,16 @@
+use crate::builtin;
+use crate::hir::Res;
 use crate::sema::diag::{Diagnostics, Error, Warning};
 use crate::sema::entity::Entity;
 use linked_hash_map::LinkedHashMap;
-use matc_span::Span;
+use matc_parser::suggest::closest;
+use matc_span::{NodeId, Span};
 use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::rc::Rc;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub(crate) struct Scope {
     parent: Option<Rc<RefCell<Self>>>,
     // Toplevel has DefVars & DefFns, otherwise, only DefVars will be held.
@@ -41,18 +46,57 @@ impl Scope {
         self.children.push(s);
     }
 
-    pub(crate) fn refer(&mut self, name: &str, span: Span) -> Result<(), Error> {
+    /// Entities defined in this scope in the order of definitions
+    pub(crate) fn entities(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.values()
+    }
+
+    /// Marks the entity as referred and returns its definition ID.
+    pub(crate) fn refer(&mut self, name: &str, span: Span) -> Result<Res, Error> {
         if let Some(var) = self.entities.get_mut(name) {
             var.referred();
-            Ok(())
-        } else {
+            Ok(Res::Def(var.id()))
+        } else if let Some(parent) = self.parent() {
             // Find the variable on the upper scope until toplevel
-            self.parent()
-                .ok_or(Error::UnresolvedRef(span))
-                .and_then(|parent| parent.borrow_mut().refer(name, span))
+            parent.borrow_mut().refer(name, span)
+        } else if builtin::lookup(name).is_some() {
+            // Builtins can be shadowed by any definitions
+            Ok(Res::Builtin)
+        } else {
+            Err(Error::UnresolvedRef(span, None))
         }
     }
 
+    /// Suggests a visible entity or builtin whose name is similar to `name`.
+    pub(crate) fn suggest(&self, name: &str) -> Option<String> {
+        let names = self.visible_names();
+        let similar = closest(name, names.iter().map(|(name, _)| name.as_str()))?;
+        let (_, kind) = names.iter().find(|(name, _)| name == similar)?;
+        Some(format!(
+            "a {} with a similar name exists: `{}`",
+            kind, similar
+        ))
+    }
+
+    /// Returns the names of the entities in this scope and its ancestors, and the builtins,
+    /// each paired with its kind.
+    fn visible_names(&self) -> Vec<(String, &'static str)> {
+        let mut names = match self.parent() {
+            Some(parent) => parent.borrow().visible_names(),
+            None => builtin::BUILTINS
+                .iter()
+                .map(|builtin| (builtin.name.to_string(), "builtin function"))
+                .collect(),
+        };
+        let kind = if self.parent.is_some() {
+            "local"
+        } else {
+            "toplevel definition"
+        };
+        names.extend(self.entities.keys().map(|name| (name.clone(), kind)));
+        names
+    }
+
     pub(crate) fn define_entity(&mut self, entity: Entity) -> Result<(), Error> {
         if let Some(dup) = self
             .entities
@@ -64,9 +108,23 @@ impl Scope {
         }
     }
 
+    /// Collects the entities of this scope and its descendants by their definition IDs.
+    pub(crate) fn collect_entities(&self, entities: &mut BTreeMap<NodeId, Entity>) {
+        entities.extend(
+            self.entities
+                .values()
+                .map(|entity| (entity.id(), entity.clone())),
+        );
+        for child in &self.children {
+            child.borrow().collect_entities(entities);
+        }
+    }
+
     pub(crate) fn check_references(&self, diag: &mut Diagnostics) {
         for ent in self.entities.values() {
-            if !ent.is_referred() {
+            // `main` is referred by the runtime
+            let is_main = self.parent.is_none() && *ent.name.value == "main";
+            if !ent.is_referred() && !is_main {
                 diag.push_warn(Warning::UnusedEntity(ent.name.span));
             }
         }
@@ -79,3 +137,13 @@ impl Scope {
         }
     }
 }
+
+impl fmt::Debug for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Skip the parent, which would print itself through its children endlessly.
+        f.debug_struct("Scope")
+            .field("entities", &self.entities)
+            .field("children", &self.children)
+            .finish()
+    }
+}
@@ -0,0 +1,273 @@
+use crate::hir::{Hir, Res};
+use crate::sema::diag::{Diagnostics, Error};
+use matc_ast::visit::{walk_expr, walk_stmt, Visitor};
+use matc_ast::{Expr, Stmt, Type};
+use matc_span::Spanned;
+use std::ops::Deref;
+
+/// Checks the types of values stored, passed, returned and converted, e.g. that functions have
+/// matching signatures.
+///
+/// Integers are converted into each other implicitly, while `void` values, string literals and
+/// functions never fit where another type is expected.
+pub(crate) struct TypeChecker<'a> {
+    hir: &'a Hir,
+    /// The return type of the function being checked
+    ret_ty: Option<&'a Type>,
+    diag: Diagnostics,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub(crate) fn new(hir: &'a Hir) -> Self {
+        Self {
+            hir,
+            ret_ty: None,
+            diag: Diagnostics::new(),
+        }
+    }
+
+    pub(crate) fn check(&mut self) -> Diagnostics {
+        for def in &self.hir.defs {
+            self.visit_stmt(def);
+        }
+        self.diag.clone()
+    }
+
+    pub(crate) fn check_expr(&mut self, expr: &'a Spanned<Expr>) -> Diagnostics {
+        self.visit_expr(expr);
+        self.diag.clone()
+    }
+
+    /// Reports `expr` unless its type can be converted into `expected`.
+    fn expect(&mut self, expected: &Type, expr: &Spanned<Expr>) {
+        // String literals have no type and are accepted only by builtins.
+        if let Expr::String(_) = expr.deref() {
+            self.diag.push_err(Error::TypeMismatch(
+                expr.span,
+                expected.clone(),
+                "a string".to_string(),
+            ));
+            return;
+        }
+        // Unknown types have been reported by the parser.
+        let Some(found) = self.hir.type_of(expr).filter(|found| !found.has_error()) else {
+            return;
+        };
+        if expected.has_error() {
+            return;
+        }
+        let is_int = |ty: &Type| matches!(ty, Type::I8 | Type::I32);
+        if !(is_int(expected) && is_int(&found)) && *expected != found {
+            self.diag.push_err(Error::TypeMismatch(
+                expr.span,
+                expected.clone(),
+                format!("`{}`", found),
+            ));
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for TypeChecker<'a> {
+    fn visit_stmt(&mut self, stmt: &'a Spanned<Stmt>) {
+        match stmt.deref() {
+            Stmt::DefFn { ret_ty, .. } => {
+                let outer = self.ret_ty.replace(ret_ty);
+                walk_stmt(self, stmt);
+                self.ret_ty = outer;
+                return;
+            }
+            Stmt::DefVar {
+                ty,
+                expr: Some(expr),
+                ..
+            } => self.expect(ty, expr),
+            Stmt::Return(Some(expr)) => {
+                if let Some(ret_ty) = self.ret_ty {
+                    self.expect(ret_ty, expr);
+                }
+            }
+            // A compound assignment stores back into its left-hand side as well.
+            Stmt::Assign(lhs, rhs)
+            | Stmt::AddAssign(lhs, rhs)
+            | Stmt::SubAssign(lhs, rhs)
+            | Stmt::MulAssign(lhs, rhs)
+            | Stmt::DivAssign(lhs, rhs)
+            | Stmt::RemAssign(lhs, rhs)
+            | Stmt::BitAndAssign(lhs, rhs)
+            | Stmt::BitOrAssign(lhs, rhs)
+            | Stmt::BitXorAssign(lhs, rhs)
+            | Stmt::ShlAssign(lhs, rhs)
+            | Stmt::ShrAssign(lhs, rhs) => {
+                if let Some(ty) = self.hir.type_of(lhs) {
+                    self.expect(&ty, rhs);
+                }
+            }
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &'a Spanned<Expr>) {
+        match expr.deref() {
+            Expr::FnCall { name, args } => {
+                if let Some(Type::Fn(fn_ty)) = self.hir.type_of(name) {
+                    // Argument counts are checked by `DereferenceChecker`.
+                    for (param, arg) in fn_ty.params.iter().zip(args) {
+                        self.expect(param, arg);
+                    }
+                }
+                // Builtins may be called although they are not values.
+                if matches!(name.deref(), Expr::Variable(_)) {
+                    for arg in args {
+                        self.visit_expr(arg);
+                    }
+                    return;
+                }
+            }
+            Expr::As(operand, ty) => self.expect(ty, operand),
+            Expr::Variable(_) => {
+                if let Some(Res::Builtin) = self.hir.resolutions.get(&expr.id) {
+                    self.diag.push_err(Error::BuiltinAsValue(expr.span));
+                }
+            }
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sema::local_resolver::LocalResolver;
+
+    fn check(code: &str) -> Vec<Error> {
+        let mut hir = Hir::from(matc_parser::parse("test.mat", code).unwrap());
+        LocalResolver::new().resolve(&mut hir);
+        TypeChecker::new(&hir).check().errors
+    }
+
+    /// The source text of each mismatch with the expected and found types
+    fn mismatches(code: &str) -> Vec<(&str, String, String)> {
+        check(code)
+            .into_iter()
+            .map(|err| match err {
+                Error::TypeMismatch(span, expected, found) => {
+                    let text = code[span.range()].trim_end();
+                    (text, expected.to_string(), found.to_string())
+                }
+                err => panic!("unexpected error: {:?}", err),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_signatures() {
+        let code = r#"
+fn double(x: i32) -> i32 {
+    return x * 2;
+}
+
+fn apply(f: fn(i32) -> i32, x: char) -> i32 {
+    return f(x);
+}
+
+let table: fn(i32) -> i32 = double;
+
+fn main() -> i32 {
+    let g: fn(fn(i32) -> i32, char) -> i32 = apply;
+    g = apply;
+    return g(table, 'a') + apply(double, 1);
+}
+"#;
+        assert!(check(code).is_empty());
+
+        let code = r#"
+fn double(x: i32) -> i32 {
+    return x * 2;
+}
+
+fn get() -> fn(i32) -> i32 {
+    return 1;
+}
+
+fn main() -> i32 {
+    let f: fn(char) -> i32 = double;
+    f = get();
+    return get()(double) + double as i32;
+}
+"#;
+        assert_eq!(
+            mismatches(code),
+            vec![
+                ("1", "fn(i32) -> i32".into(), "`i32`".into()),
+                (
+                    "double",
+                    "fn(char) -> i32".into(),
+                    "`fn(i32) -> i32`".into()
+                ),
+                ("get()", "fn(char) -> i32".into(), "`fn(i32) -> i32`".into()),
+                ("double", "i32".into(), "`fn(i32) -> i32`".into()),
+                ("double", "i32".into(), "`fn(i32) -> i32`".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_void_and_string() {
+        let code = r#"
+fn f() -> void {}
+
+fn g(c: char) -> i32 {
+    let s: i32 = "abc";
+    let x: i32 = f();
+    x = print_int(1);
+    return g("d" as char) + g(f());
+}
+"#;
+        assert_eq!(
+            mismatches(code),
+            vec![
+                ("\"abc\"", "i32".into(), "a string".into()),
+                ("f()", "i32".into(), "`void`".into()),
+                ("print_int(1)", "i32".into(), "`void`".into()),
+                ("\"d\"", "char".into(), "a string".into()),
+                ("f()", "char".into(), "`void`".into()),
+            ]
+        );
+        assert!(check("fn f() -> void {}\nfn g() -> void {\n    return f();\n}\n").is_empty());
+    }
+
+    #[test]
+    fn test_compound_assign() {
+        let code = r#"
+fn f() -> void {}
+
+fn g(c: char) -> i32 {
+    let x: i32 = 1;
+    x += c;
+    c <<= x;
+    x -= f();
+    c *= "e";
+    x %= print_int(x);
+    return x;
+}
+"#;
+        assert_eq!(
+            mismatches(code),
+            vec![
+                ("f()", "i32".into(), "`void`".into()),
+                ("\"e\"", "char".into(), "a string".into()),
+                ("print_int(x)", "i32".into(), "`void`".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builtin_as_value() {
+        let code = "fn f() -> i32 {\n    let g: i32 = putchar;\n    return putchar(g);\n}\n";
+        let errors = check(code);
+        assert!(matches!(errors[..], [Error::BuiltinAsValue(_)]));
+        assert_eq!(&code[errors[0].span().range()], "putchar");
+    }
+}
@@ -1,14 +0,0 @@
-// pub(crate) struct AstVisitor;
-//
-// impl AstVisitor {
-//     pub(crate) fn visit_expr() {}
-//     pub(crate) fn visit_stmt() {}
-// }
-//
-// pub(crate) trait Visitor<T> {
-//     fn visit()
-//
-//     fn accept(&self, visitor: AstVisitor) {
-//         visitor.visit(self);
-//     }
-// }